@@ -0,0 +1,95 @@
+// Compares instruction decoding with and without DecodeCache by booting xv6.
+// With decode_bench feature, also compares the table-driven decoder with the
+// linear scan decoder by decoding the words in the xv6 kernel image.
+//
+// Run with
+//   $ cargo bench --bench decode [cycles]
+//   $ cargo bench --bench decode --features decode_bench [cycles]
+
+extern crate riscv_emu_rust;
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+use riscv_emu_rust::Emulator;
+use riscv_emu_rust::default_terminal::DefaultTerminal;
+
+const DEFAULT_CYCLES: u64 = 100_000_000;
+const KERNEL_PATH: &str = "resources/xv6/kernel";
+const FILESYSTEM_PATH: &str = "resources/xv6/fs.img";
+
+fn read_file(path: &str) -> Vec<u8> {
+	let mut file = File::open(path).unwrap();
+	let mut data = vec![];
+	file.read_to_end(&mut data).unwrap();
+	data
+}
+
+fn run(decode_cache_enabled: bool, cycles: u64) -> Duration {
+	let mut emulator = Emulator::new(Box::new(DefaultTerminal::new()));
+	emulator.setup_program(read_file(KERNEL_PATH)).unwrap();
+	emulator.setup_filesystem(read_file(FILESYSTEM_PATH));
+	emulator.enable_decode_cache(decode_cache_enabled);
+	let start = Instant::now();
+	for _i in 0..cycles {
+		emulator.tick();
+	}
+	start.elapsed()
+}
+
+// Decodes 32-bit words in the kernel image until `count` words are decoded
+// and returns the elapsed time and the sum of the decoded indices, which
+// keeps the decoding from being optimized out.
+#[cfg(feature = "decode_bench")]
+fn decode_words<F: Fn(u32) -> Option<usize>>(decode: F, words: &[u32], count: u64) -> (Duration, usize) {
+	let mut sum = 0;
+	let start = Instant::now();
+	for i in 0..count {
+		if let Some(index) = decode(words[(i % words.len() as u64) as usize]) {
+			sum += index;
+		}
+	}
+	(start.elapsed(), sum)
+}
+
+#[cfg(feature = "decode_bench")]
+fn compare_decoders(count: u64) {
+	use riscv_emu_rust::cpu::decode_bench::{decode_linear, TableDecoder};
+
+	// Uncompressed instruction candidates
+	let words = read_file(KERNEL_PATH).chunks_exact(4)
+		.map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+		.filter(|word| (word & 0x3) == 0x3)
+		.collect::<Vec<u32>>();
+	let table = TableDecoder::new();
+	let results = [
+		("linear scan find", decode_words(decode_linear, &words, count)),
+		("table find", decode_words(|word| table.decode(word), &words, count))
+	];
+	assert_eq!(results[0].1.1, results[1].1.1);
+	for &(name, (elapsed, _sum)) in results.iter() {
+		println!("{:<24}{} words in {:?} ({:.2} M words/s)", name, count, elapsed,
+			count as f64 / elapsed.as_secs_f64() / 1_000_000.0);
+	}
+}
+
+#[cfg(not(feature = "decode_bench"))]
+fn compare_decoders(_count: u64) {
+	println!("Run with --features decode_bench to compare with the linear scan decoder");
+}
+
+fn main() {
+	// `cargo bench` passes "--bench" so ignores non-number arguments
+	let cycles = env::args().skip(1)
+		.filter_map(|arg| arg.parse::<u64>().ok())
+		.next()
+		.unwrap_or(DEFAULT_CYCLES);
+	for &(name, enabled) in [("table", false), ("table + decode cache", true)].iter() {
+		let elapsed = run(enabled, cycles);
+		println!("{:<24}{} cycles in {:?} ({:.2} MIPS)", name, cycles, elapsed,
+			cycles as f64 / elapsed.as_secs_f64() / 1_000_000.0);
+	}
+	compare_decoders(cycles);
+}
@@ -6,6 +6,7 @@ mod dummy_terminal;
 
 use riscv_emu_rust::Emulator;
 use riscv_emu_rust::cpu::Xlen;
+use riscv_emu_rust::mmu::MisalignedAccessPolicy;
 use riscv_emu_rust::terminal::Terminal;
 use popup_terminal::PopupTerminal;
 use dummy_terminal::DummyTerminal;
@@ -13,6 +14,7 @@ use dummy_terminal::DummyTerminal;
 use std::env;
 use std::fs::File;
 use std::io::Read;
+use std::path::PathBuf;
 
 use getopts::Options;
 
@@ -43,7 +45,11 @@ fn main () -> std::io::Result<()> {
 	opts.optopt("d", "dtb", "Device tree file", "linux/dtb");
 	opts.optflag("n", "no_terminal", "No popup terminal");
 	opts.optflag("h", "help", "Show this help menu");
-	opts.optflag("p", "page_cache", "Enable experimental page cache optimization");
+	opts.optflag("s", "strict", "Panic on undecodable instruction instead of trapping");
+	opts.optopt("", "misaligned", "Set how misaligned loads and stores are handled. Default is emulate", "emulate|trap");
+	opts.optopt("", "vlen", "Set vector register length in bits. Default is 128", "64|128|...|65536");
+	opts.optopt("", "cache-block-size", "Set cache block size in bytes for cache-block operations. Default is 64", "8|16|...|4096");
+	opts.optopt("", "semihosting", "Enable semihosting. The program can open files only in the directory", "dir");
 
 	let matches = match opts.parse(&args[1..]) {
 		Ok(m) => m,
@@ -102,7 +108,13 @@ fn main () -> std::io::Result<()> {
 	};
 
 	let mut emulator = Emulator::new(get_terminal(terminal_type));
-	emulator.setup_program(elf_contents);
+	match emulator.setup_program(elf_contents) {
+		Ok(()) => {},
+		Err(e) => {
+			println!("{}", e);
+			return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+		}
+	};
 	
 	match matches.opt_str("x") {
 		Some(x) => match x.as_str() {
@@ -127,9 +139,51 @@ fn main () -> std::io::Result<()> {
 	if has_dtb {
 		emulator.setup_dtb(dtb_contents);
 	}
-	if matches.opt_present("p") {
-		emulator.enable_page_cache(true);
+	if matches.opt_present("s") {
+		emulator.enable_strict_mode(true);
 	}
+	match matches.opt_str("misaligned") {
+		Some(policy) => match policy.as_str() {
+			"emulate" => emulator.set_misaligned_access_policy(MisalignedAccessPolicy::Emulate),
+			"trap" => emulator.set_misaligned_access_policy(MisalignedAccessPolicy::Trap),
+			_ => {
+				print_usage(&program, opts);
+				// @TODO: throw error?
+				return Ok(());
+			}
+		},
+		None => {}
+	};
+	match matches.opt_str("vlen") {
+		Some(vlen) => match vlen.parse::<usize>() {
+			Ok(vlen) if vlen.is_power_of_two() && (64..=65536).contains(&vlen) => emulator.set_vlen(vlen),
+			_ => {
+				print_usage(&program, opts);
+				// @TODO: throw error?
+				return Ok(());
+			}
+		},
+		None => {}
+	};
+	match matches.opt_str("cache-block-size") {
+		Some(size) => match size.parse::<u64>() {
+			Ok(size) if size.is_power_of_two() && (8..=4096).contains(&size) => emulator.set_cache_block_size(size),
+			_ => {
+				print_usage(&program, opts);
+				// @TODO: throw error?
+				return Ok(());
+			}
+		},
+		None => {}
+	};
+	match matches.opt_str("semihosting") {
+		// Passing program file and the following free arguments as command line
+		Some(dir) => emulator.setup_semihosting(PathBuf::from(dir), matches.free.join(" ")),
+		None => {}
+	};
 	emulator.run();
-	Ok(())
+	match emulator.get_exit_code() {
+		Some(code) => std::process::exit(code),
+		None => Ok(())
+	}
 }
@@ -6,7 +6,9 @@ pub struct Clint {
 	clock: u64,
 	msip: u32,
 	mtimecmp: u64,
-	mtime: u64
+	mtime: u64,
+	// mip bits driven at the last cycle
+	ip: u64
 }
 
 impl Clint {
@@ -16,12 +18,15 @@ impl Clint {
 			clock: 0,
 			msip: 0,
 			mtimecmp: 0,
-			mtime: 0 // @TODO: Should be bound to csr time register
+			mtime: 0, // @TODO: Should be bound to csr time register
+			ip: 0
 		}
 	}
 
 	/// Runs one cycle. `Clint` can raise interrupt. If it does it rises a certain bit
-	/// depending on interrupt type of CPU `mip` register.
+	/// depending on interrupt type of CPU `mip` register. The interrupts are
+	/// level-triggered, so the bit stays pending until `msip` or `mtimecmp`
+	/// is updated and then `Clint` clears it.
 	///
 	/// # Arguments
 	/// * `mip` CPU `mip` register. It can be updated if interrupt occurs.
@@ -29,12 +34,18 @@ impl Clint {
 		self.clock = self.clock.wrapping_add(1);
 		self.mtime = self.mtime.wrapping_add(1);
 
+		let mut ip = 0;
 		if (self.msip & 1) != 0 {
-			*mip |= MIP_MSIP;
+			ip |= MIP_MSIP;
 		}
-
 		if self.mtimecmp > 0 && self.mtime >= self.mtimecmp {
-			*mip |= MIP_MTIP;
+			ip |= MIP_MTIP;
+		}
+
+		// Updates mip only when the interrupt signals change
+		if ip != self.ip {
+			*mip = (*mip & !(MIP_MSIP | MIP_MTIP)) | ip;
+			self.ip = ip;
 		}
 	}
 
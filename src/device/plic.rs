@@ -1,5 +1,3 @@
-use cpu::MIP_SEIP;
-
 // Based on SiFive Interrupt Cookbook
 // https://sifive.cdn.prismic.io/sifive/0d163928-2128-42be-a75a-464df65e04e0_sifive-interrupt-cookbook.pdf
 
@@ -38,14 +36,13 @@ impl Plic {
 
 	/// Runs one cycle. Takes interrupting signals from devices and
 	/// raises an interrupt to CPU depending on configuration.
-	/// If interrupt occurs a certain bit of `mip` regiser is risen
-	/// depending on interrupt type.
+	/// The interrupt signal stays asserted until the interrupt is
+	/// completed. See [`is_interrupting`](#method.is_interrupting).
 	///
 	/// # Arguments
 	/// * `virtio_ip`
 	/// * `uart_ip`
-	/// * `mip`
-	pub fn tick(&mut self, virtio_ip: bool, uart_ip: bool, mip: &mut u64) {
+	pub fn tick(&mut self, virtio_ip: bool, uart_ip: bool) {
 		self.clock = self.clock.wrapping_add(1);
 
 		// Handling interrupts as "Edge-triggered" interrupt so far
@@ -67,12 +64,19 @@ impl Plic {
 		}
 
 		if self.needs_update_irq {
-			self.update_irq(mip);
+			self.update_irq();
 			self.needs_update_irq = false;
 		}
 	}
 
-	fn update_irq(&mut self, mip: &mut u64) {
+	/// Returns whether the external interrupt signal to CPU is asserted.
+	/// CPU reads `mip.SEIP` as the logical OR of the signal and
+	/// the bit written by software.
+	pub fn is_interrupting(&self) -> bool {
+		self.irq != 0
+	}
+
+	fn update_irq(&mut self) {
 		// Hardcoded VirtIO and UART
 		// @TODO: Should be configurable with device tree
 
@@ -104,21 +108,18 @@ impl Plic {
 		}
 
 		self.irq = irq;
-		if self.irq != 0 {
-			//println!("IRQ: {:X}", self.irq);
-			*mip |= MIP_SEIP;
-		}
+		//println!("IRQ: {:X}", self.irq);
 	}
 
 	fn set_ip(&mut self, irq: u32) {
 		let index = (irq >> 3) as usize;
-		self.ips[index] = self.ips[index] | (1 << irq);
+		self.ips[index] = self.ips[index] | (1 << (irq & 7));
 		self.needs_update_irq = true;
 	}
 
 	fn clear_ip(&mut self, irq: u32) {
 		let index = (irq >> 3) as usize;
-		self.ips[index] = self.ips[index] & !(1 << irq);
+		self.ips[index] = self.ips[index] & !(1 << (irq & 7));
 		self.needs_update_irq = true;
 	}
 
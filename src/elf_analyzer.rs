@@ -2,6 +2,72 @@ extern crate fnv;
 
 use self::fnv::FnvHashMap;
 
+use std::fmt;
+
+/// `e_machine` value of RISC-V
+const EM_RISCV: u16 = 0xf3;
+
+/// `p_type` value of loadable segment
+pub const PT_LOAD: u32 = 1;
+
+/// `e_flags` bit of the program for RV32E or RV64E
+pub const EF_RISCV_RVE: u32 = 0x8;
+
+/// Error returned when program binary can't be loaded
+#[derive(Debug, PartialEq)]
+pub enum LoadError {
+	/// The content doesn't start with ELF magic number
+	BadMagic,
+
+	/// `EI_CLASS` is neither 32-bit nor 64-bit
+	UnsupportedClass(u8),
+
+	/// `e_machine` is not RISC-V
+	UnsupportedMachine(u16),
+
+	/// ELF header, program header table, or section header table
+	/// runs past the end of the file
+	TruncatedHeader,
+
+	/// The content of the section, specified by index, runs past the end of the file
+	SectionOutOfBounds(usize),
+
+	/// The file content of the loadable segment, specified by index, runs past
+	/// the end of the file or is larger than its memory size
+	SegmentOutOfBounds(usize),
+
+	/// The file has no loadable segment
+	NoLoadableSegment,
+
+	/// The loadable segment, specified by physical address, doesn't fit in the emulated RAM
+	SegmentOutsideRam(u64),
+
+	/// The loadable segment, specified by physical address, overlaps with another one
+	OverlappingSegments(u64),
+
+	/// Entry point address is not in the emulated RAM
+	EntryPointOutsideRam(u64)
+}
+
+impl fmt::Display for LoadError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			LoadError::BadMagic => write!(f, "This file does not seem ELF file"),
+			LoadError::UnsupportedClass(class) => write!(f, "Unsupported ELF class {:X}", class),
+			LoadError::UnsupportedMachine(machine) => write!(f, "Not RISC-V ELF file. e_machine:{:X}", machine),
+			LoadError::TruncatedHeader => write!(f, "ELF header is truncated"),
+			LoadError::SectionOutOfBounds(index) => write!(f, "Section {} runs past the end of the file", index),
+			LoadError::SegmentOutOfBounds(index) => write!(f, "Segment {} runs past the end of the file", index),
+			LoadError::NoLoadableSegment => write!(f, "No loadable segment"),
+			LoadError::SegmentOutsideRam(address) => write!(f, "Segment at {:X} is outside of RAM", address),
+			LoadError::OverlappingSegments(address) => write!(f, "Segment at {:X} overlaps with another segment", address),
+			LoadError::EntryPointOutsideRam(address) => write!(f, "Entry point {:X} is outside of RAM", address)
+		}
+	}
+}
+
+impl std::error::Error for LoadError {}
+
 /// ELF header
 pub struct Header {
 	pub e_width: u8, // 32 or 64
@@ -14,26 +80,26 @@ pub struct Header {
 	_e_machine: u16,
 	_e_version: u32,
 	pub e_entry: u64,
-	_e_phoff: u64,
+	e_phoff: u64,
 	e_shoff: u64,
-	_e_flags: u32,
+	pub e_flags: u32,
 	_e_ehsize: u16,
 	_e_phentsize: u16,
-	_e_phnum: u16,
+	e_phnum: u16,
 	_e_shentsize: u16,
 	e_shnum: u16,
 	_e_shstrndx: u16
 }
 
 /// ELF program header
-pub struct _ProgramHeader {
-	_p_type: u32,
+pub struct ProgramHeader {
+	pub p_type: u32,
 	_p_flags: u32,
-	_p_offset: u64,
-	_p_vaddr: u64,
-	_p_paddr: u64,
-	_p_filesz: u64,
-	_p_memsz: u64,
+	pub p_offset: u64,
+	pub p_vaddr: u64,
+	pub p_paddr: u64,
+	pub p_filesz: u64,
+	pub p_memsz: u64,
 	_p_align: u64
 }
 
@@ -89,16 +155,33 @@ impl ElfAnalyzer {
 		true
 	}
 
-	/// Reads ELF header
-	pub fn read_header(&self) -> Header {
+	/// Reads ELF header. Returns `Err` if the content isn't RISC-V ELF file
+	/// or the header is truncated.
+	pub fn read_header(&self) -> Result<Header, LoadError> {
+		if !self.validate() {
+			return Err(LoadError::BadMagic);
+		}
+
+		if self.data.len() < 0x10 {
+			return Err(LoadError::TruncatedHeader);
+		}
+
 		let e_class = self.read_byte(4);
 
 		let e_width = match e_class {
 			1 => 32,
 			2 => 64,
-			_ => panic!("Unknown e_class:{:X}", e_class)
+			_ => return Err(LoadError::UnsupportedClass(e_class))
 		};
 
+		let header_size = match e_width {
+			64 => 0x40,
+			_ => 0x34
+		};
+		if self.data.len() < header_size {
+			return Err(LoadError::TruncatedHeader);
+		}
+
 		let e_endian = self.read_byte(5);
 		let e_elf_version = self.read_byte(6);
 		let e_osabi = self.read_byte(7);
@@ -112,6 +195,10 @@ impl ElfAnalyzer {
 		let e_machine = self.read_halfword(offset);
 		offset += 2;
 
+		if e_machine != EM_RISCV {
+			return Err(LoadError::UnsupportedMachine(e_machine));
+		}
+
 		let e_version = self.read_word(offset);
 		offset += 4;
 
@@ -196,7 +283,7 @@ impl ElfAnalyzer {
 		println!("e_shstrndx:{:X}", e_shstrndx);
 		*/
 
-		Header {
+		Ok(Header {
 			e_width: e_width,
 			_e_class: e_class,
 			_e_endian: e_endian,
@@ -207,26 +294,35 @@ impl ElfAnalyzer {
 			_e_machine: e_machine,
 			_e_version: e_version,
 			e_entry: e_entry,
-			_e_phoff: e_phoff,
+			e_phoff: e_phoff,
 			e_shoff: e_shoff,
-			_e_flags: e_flags,
+			e_flags: e_flags,
 			_e_ehsize: e_ehsize,
 			_e_phentsize: e_phentsize,
-			_e_phnum: e_phnum,
+			e_phnum: e_phnum,
 			_e_shentsize: e_shentsize,
 			e_shnum: e_shnum,
 			_e_shstrndx: e_shstrndx
-		}
+		})
 	}
 
-	/// Reads ELF program headers
+	/// Reads ELF program headers. Returns `Err` if the program header table
+	/// or the file content of a loadable segment runs past the end of the file.
 	///
 	/// # Arguments
 	/// * `header`
-	pub fn _read_program_headers(&self, header: &Header) -> Vec<_ProgramHeader> {
+	pub fn read_program_headers(&self, header: &Header) -> Result<Vec<ProgramHeader>, LoadError> {
+		let entry_size = match header.e_width {
+			64 => 0x38,
+			_ => 0x20
+		};
+		if !self.is_in_bounds(header.e_phoff, header.e_phnum as u64 * entry_size) {
+			return Err(LoadError::TruncatedHeader);
+		}
+
 		let mut headers = Vec::new();
-		let mut offset = header._e_phoff as usize;
-		for _i in 0..header._e_phnum {
+		let mut offset = header.e_phoff as usize;
+		for i in 0..header.e_phnum {
 			let p_type = self.read_word(offset);
 			offset += 4;
 
@@ -236,94 +332,22 @@ impl ElfAnalyzer {
 				offset += 4;
 			}
 
-			let p_offset = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let p_offset = self.read_address(header, &mut offset);
 
-			let p_vaddr = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let p_vaddr = self.read_address(header, &mut offset);
 
-			let p_paddr = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let p_paddr = self.read_address(header, &mut offset);
 
-			let p_filesz = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let p_filesz = self.read_address(header, &mut offset);
 
-			let p_memsz = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let p_memsz = self.read_address(header, &mut offset);
 
 			if header.e_width == 32 {
 				p_flags = self.read_word(offset);
 				offset += 4;
 			}
 
-			let p_align = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let p_align = self.read_address(header, &mut offset);
 
 			/*
 			println!("");
@@ -339,90 +363,55 @@ impl ElfAnalyzer {
 			println!("p_align:{:X}", p_align);
 			*/
 
-			headers.push(_ProgramHeader{
-				_p_type: p_type,
+			headers.push(ProgramHeader{
+				p_type: p_type,
 				_p_flags: p_flags,
-				_p_offset: p_offset,
-				_p_vaddr: p_vaddr,
-				_p_paddr: p_paddr,
-				_p_filesz: p_filesz,
-				_p_memsz: p_memsz,
+				p_offset: p_offset,
+				p_vaddr: p_vaddr,
+				p_paddr: p_paddr,
+				p_filesz: p_filesz,
+				p_memsz: p_memsz,
 				_p_align: p_align
 			});
+
+			if p_type == PT_LOAD && (p_filesz > p_memsz || !self.is_in_bounds(p_offset, p_filesz)) {
+				return Err(LoadError::SegmentOutOfBounds(i as usize));
+			}
 		}
 
-		headers
+		Ok(headers)
 	}
 
-	/// Reads ELF section headers
+	/// Reads ELF section headers. Returns `Err` if the section header table
+	/// or the content of a section runs past the end of the file.
 	///
 	/// # Arguments
 	/// * `header`
-	pub fn read_section_headers(&self, header: &Header) -> Vec<SectionHeader> {
+	pub fn read_section_headers(&self, header: &Header) -> Result<Vec<SectionHeader>, LoadError> {
+		let entry_size = match header.e_width {
+			64 => 0x40,
+			_ => 0x28
+		};
+		if !self.is_in_bounds(header.e_shoff, header.e_shnum as u64 * entry_size) {
+			return Err(LoadError::TruncatedHeader);
+		}
+
 		let mut headers = Vec::new();
 		let mut offset = header.e_shoff as usize;
-		for _i in 0..header.e_shnum {
+		for i in 0..header.e_shnum {
 			let sh_name = self.read_word(offset);
 			offset += 4;
 
 			let sh_type = self.read_word(offset);
 			offset += 4;
 
-			let sh_flags = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let sh_flags = self.read_address(header, &mut offset);
 
-			let sh_addr = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let sh_addr = self.read_address(header, &mut offset);
 
-			let sh_offset = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let sh_offset = self.read_address(header, &mut offset);
 
-			let sh_size = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let sh_size = self.read_address(header, &mut offset);
 
 			let sh_link = self.read_word(offset);
 			offset += 4;
@@ -430,37 +419,13 @@ impl ElfAnalyzer {
 			let sh_info = self.read_word(offset);
 			offset += 4;
 
-			let sh_addralign = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let sh_addralign = self.read_address(header, &mut offset);
 
-			let sh_entsize = match header.e_width {
-				64 => {
-					let data = self.read_doubleword(offset);
-					offset += 8;
-					data
-				},
-				32 => {
-					let data = self.read_word(offset);
-					offset += 4;
-					data as u64
-				},
-				_ => panic!("Not happen")
-			};
+			let sh_entsize = self.read_address(header, &mut offset);
 
 			/*
 			println!("");
-			println!("Section:{:X}", _i);
+			println!("Section:{:X}", i);
 			println!("sh_name:{:X}", sh_name);
 			println!("sh_type:{:X}", sh_type);
 			println!("sh_flags:{:X}", sh_flags);
@@ -485,9 +450,14 @@ impl ElfAnalyzer {
 				_sh_addralign: sh_addralign,
 				_sh_entsize: sh_entsize
 			});
+
+			// SHT_NULL and SHT_NOBITS sections have no content in the file
+			if sh_type != 0 && sh_type != 8 && !self.is_in_bounds(sh_offset, sh_size) {
+				return Err(LoadError::SectionOutOfBounds(i as usize));
+			}
 		}
 
-		headers
+		Ok(headers)
 	}
 
 	/// Reads symbol entries of symbol table sections
@@ -507,8 +477,7 @@ impl ElfAnalyzer {
 
 			let entry_size = match header.e_width {
 				64 => 24,
-				32 => 16,
-				_ => panic!("Not happen")
+				_ => 16
 			};
 
 			for _j in 0..(sh_size / entry_size) {
@@ -539,7 +508,7 @@ impl ElfAnalyzer {
 						_st_size = self.read_doubleword(offset);
 						offset += 8;
 					},
-					32 => {
+					_ => {
 						st_name = self.read_word(offset);
 						offset += 4;
 
@@ -557,8 +526,7 @@ impl ElfAnalyzer {
 
 						_st_shndx = self.read_halfword(offset);
 						offset += 2;
-					},
-					_ => panic!("No happen")
+					}
 				};
 
 				/*
@@ -674,6 +642,39 @@ impl ElfAnalyzer {
 		None
 	}
 
+	/// Checks if `size` bytes from `offset` are in ELF file content
+	///
+	/// # Arguments
+	/// * `offset`
+	/// * `size`
+	fn is_in_bounds(&self, offset: u64, size: u64) -> bool {
+		match offset.checked_add(size) {
+			Some(end) => end <= self.data.len() as u64,
+			None => false
+		}
+	}
+
+	/// Reads an address sized data, four bytes for 32-bit ELF and
+	/// eight bytes for 64-bit ELF, from ELF file content and advances `offset`.
+	///
+	/// # Arguments
+	/// * `header`
+	/// * `offset`
+	fn read_address(&self, header: &Header, offset: &mut usize) -> u64 {
+		match header.e_width {
+			64 => {
+				let data = self.read_doubleword(*offset);
+				*offset += 8;
+				data
+			},
+			_ => {
+				let data = self.read_word(*offset);
+				*offset += 4;
+				data as u64
+			}
+		}
+	}
+
 	/// Reads a byte from ELF file content
 	///
 	/// # Arguments
@@ -0,0 +1,1179 @@
+/// Invalid operation exception flag. The bit positions of the flags
+/// are the same as `fflags` CSR.
+pub const FLAG_NV: u8 = 0x10;
+
+/// Divide by zero exception flag
+pub const FLAG_DZ: u8 = 0x8;
+
+/// Overflow exception flag
+pub const FLAG_OF: u8 = 0x4;
+
+/// Underflow exception flag
+pub const FLAG_UF: u8 = 0x2;
+
+/// Inexact exception flag
+pub const FLAG_NX: u8 = 0x1;
+
+/// IEEE 754 binary floating-point format. Floating-point values are passed
+/// as raw bits in `u64`, the lower bits are used for narrower formats.
+pub struct Format {
+	exponent_width: u32,
+	fraction_width: u32
+}
+
+/// IEEE 754 binary32, single-precision
+pub const SINGLE: Format = Format {
+	exponent_width: 8,
+	fraction_width: 23
+};
+
+/// IEEE 754 binary64, double-precision
+pub const DOUBLE: Format = Format {
+	exponent_width: 11,
+	fraction_width: 52
+};
+
+/// Rounding modes. The discriminants are the same as `frm` CSR encoding.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoundingMode {
+	NearestEven,
+	TowardZero,
+	Down,
+	Up,
+	NearestMaxMagnitude
+}
+
+/// Error of reserved rounding mode encodings. `Cpu` raises IllegalInstruction
+/// trap for it.
+#[derive(Debug)]
+pub struct InvalidRoundingMode;
+
+/// Returns `RoundingMode` from `frm` CSR or instruction `rm` field encoding.
+/// Returns `Err` for reserved encodings and dynamic rounding mode (7).
+///
+/// # Arguments
+/// * `rm`
+pub fn get_rounding_mode(rm: u64) -> Result<RoundingMode, InvalidRoundingMode> {
+	match rm {
+		0 => Ok(RoundingMode::NearestEven),
+		1 => Ok(RoundingMode::TowardZero),
+		2 => Ok(RoundingMode::Down),
+		3 => Ok(RoundingMode::Up),
+		4 => Ok(RoundingMode::NearestMaxMagnitude),
+		_ => Err(InvalidRoundingMode)
+	}
+}
+
+/// Finite floating-point value, (-1)^`sign` * `significand` * 2^`exponent`
+struct Unpacked {
+	sign: bool,
+	exponent: i32,
+	significand: u128
+}
+
+impl Format {
+	fn bias(&self) -> i32 {
+		(1 << (self.exponent_width - 1)) - 1
+	}
+
+	fn max_exponent(&self) -> u64 {
+		(1 << self.exponent_width) - 1
+	}
+
+	fn min_exponent(&self) -> i32 {
+		1 - self.bias()
+	}
+
+	fn sign_bit(&self) -> u64 {
+		1 << (self.exponent_width + self.fraction_width)
+	}
+
+	fn fraction_mask(&self) -> u64 {
+		(1 << self.fraction_width) - 1
+	}
+
+	fn get_sign(&self, value: u64) -> bool {
+		(value & self.sign_bit()) != 0
+	}
+
+	fn get_exponent(&self, value: u64) -> u64 {
+		(value >> self.fraction_width) & self.max_exponent()
+	}
+
+	fn get_fraction(&self, value: u64) -> u64 {
+		value & self.fraction_mask()
+	}
+
+	/// Returns the default NaN generated by invalid operations.
+	pub fn canonical_nan(&self) -> u64 {
+		(self.max_exponent() << self.fraction_width) | (1 << (self.fraction_width - 1))
+	}
+
+	/// Returns infinity.
+	///
+	/// # Arguments
+	/// * `sign` `true` for negative
+	pub fn infinity(&self, sign: bool) -> u64 {
+		self.pack_sign(sign) | (self.max_exponent() << self.fraction_width)
+	}
+
+	/// Returns zero.
+	///
+	/// # Arguments
+	/// * `sign` `true` for negative
+	pub fn zero(&self, sign: bool) -> u64 {
+		self.pack_sign(sign)
+	}
+
+	/// Returns `value` with the sign flipped.
+	///
+	/// # Arguments
+	/// * `value`
+	pub fn negate(&self, value: u64) -> u64 {
+		value ^ self.sign_bit()
+	}
+
+	fn max_finite(&self, sign: bool) -> u64 {
+		self.infinity(sign) - 1
+	}
+
+	fn pack_sign(&self, sign: bool) -> u64 {
+		match sign {
+			true => self.sign_bit(),
+			false => 0
+		}
+	}
+
+	pub fn is_nan(&self, value: u64) -> bool {
+		self.get_exponent(value) == self.max_exponent() && self.get_fraction(value) != 0
+	}
+
+	pub fn is_signaling_nan(&self, value: u64) -> bool {
+		self.is_nan(value) && (value & (1 << (self.fraction_width - 1))) == 0
+	}
+
+	pub fn is_infinite(&self, value: u64) -> bool {
+		self.get_exponent(value) == self.max_exponent() && self.get_fraction(value) == 0
+	}
+
+	pub fn is_zero(&self, value: u64) -> bool {
+		(value & (self.sign_bit() - 1)) == 0
+	}
+
+	/// Unpacks finite value. Subnormal significand isn't normalized.
+	fn unpack(&self, value: u64) -> Unpacked {
+		let exponent = self.get_exponent(value);
+		let fraction = self.get_fraction(value) as u128;
+		match exponent {
+			0 => Unpacked {
+				sign: self.get_sign(value),
+				exponent: self.min_exponent() - self.fraction_width as i32,
+				significand: fraction
+			},
+			_ => Unpacked {
+				sign: self.get_sign(value),
+				exponent: exponent as i32 - self.bias() - self.fraction_width as i32,
+				significand: fraction | (1 << self.fraction_width)
+			}
+		}
+	}
+
+	/// Rounds (-1)^`sign` * `significand` * 2^`exponent` and packs it into
+	/// this format. The least significant bit of `significand` must be
+	/// "sticky", which is set if any nonzero bits have been shifted out.
+	/// Tininess is detected after rounding as RISC-V specifies.
+	fn round_pack(&self, sign: bool, exponent: i32, significand: u128,
+		rounding_mode: RoundingMode, flags: &mut u8) -> u64 {
+		if significand == 0 {
+			return self.zero(sign);
+		}
+		let precision = self.fraction_width as i32 + 1;
+		let width = 128 - significand.leading_zeros() as i32;
+		let msb_exponent = exponent + width - 1;
+		let min_exponent = self.min_exponent();
+
+		// Subnormal values are rounded at the fixed minimum quantum
+		let quantum = match msb_exponent < min_exponent {
+			true => min_exponent - self.fraction_width as i32,
+			false => msb_exponent - self.fraction_width as i32
+		};
+		let (mut mantissa, inexact) = round_significand(sign, exponent, significand, quantum, rounding_mode);
+		let mut quantum = quantum;
+		if mantissa == (1 << precision) {
+			mantissa >>= 1;
+			quantum += 1;
+		}
+
+		if inexact {
+			*flags |= FLAG_NX;
+			// Tiny if the result rounded with unbounded exponent range
+			// is less than the minimum normal value
+			if msb_exponent < min_exponent {
+				let (unbounded, _) = round_significand(sign, exponent, significand,
+					msb_exponent - self.fraction_width as i32, rounding_mode);
+				if msb_exponent != min_exponent - 1 || unbounded != (1 << precision) {
+					*flags |= FLAG_UF;
+				}
+			}
+		}
+
+		match mantissa >= (1 << self.fraction_width) {
+			true => {
+				let biased_exponent = (quantum + self.fraction_width as i32 + self.bias()) as u64;
+				if biased_exponent >= self.max_exponent() {
+					*flags |= FLAG_OF | FLAG_NX;
+					let to_infinity = match rounding_mode {
+						RoundingMode::NearestEven | RoundingMode::NearestMaxMagnitude => true,
+						RoundingMode::TowardZero => false,
+						RoundingMode::Down => sign,
+						RoundingMode::Up => !sign
+					};
+					return match to_infinity {
+						true => self.infinity(sign),
+						false => self.max_finite(sign)
+					};
+				}
+				self.pack_sign(sign) | (biased_exponent << self.fraction_width) |
+					(mantissa as u64 & self.fraction_mask())
+			},
+			false => self.pack_sign(sign) | mantissa as u64
+		}
+	}
+
+	/// Returns canonical NaN if either operand is NaN. Raises invalid
+	/// operation exception if either operand is signaling NaN.
+	fn propagate_nan(&self, values: &[u64], flags: &mut u8) -> Option<u64> {
+		let mut has_nan = false;
+		for &value in values.iter() {
+			if self.is_signaling_nan(value) {
+				*flags |= FLAG_NV;
+			}
+			if self.is_nan(value) {
+				has_nan = true;
+			}
+		}
+		match has_nan {
+			true => Some(self.canonical_nan()),
+			false => None
+		}
+	}
+}
+
+/// Rounds `significand` * 2^`exponent` to a multiple of 2^`quantum` and
+/// returns the multiplier and whether the result is inexact.
+fn round_significand(sign: bool, exponent: i32, significand: u128, quantum: i32,
+	rounding_mode: RoundingMode) -> (u128, bool) {
+	let shift = quantum - exponent;
+	if shift <= 0 {
+		return (significand << -shift, false);
+	}
+	let (mantissa, remainder, half) = match shift {
+		shift if shift > 128 => (0, 1, 2),
+		128 => (0, significand, 1 << 127),
+		_ => (significand >> shift, significand & ((1 << shift) - 1), 1 << (shift - 1))
+	};
+	let inexact = remainder != 0;
+	let round_up = match rounding_mode {
+		RoundingMode::NearestEven => remainder > half || (remainder == half && (mantissa & 1) == 1),
+		RoundingMode::TowardZero => false,
+		RoundingMode::Down => inexact && sign,
+		RoundingMode::Up => inexact && !sign,
+		RoundingMode::NearestMaxMagnitude => remainder >= half
+	};
+	match round_up {
+		true => (mantissa + 1, inexact),
+		false => (mantissa, inexact)
+	}
+}
+
+/// Shifts right keeping shifted out nonzero bits in the least significant bit.
+fn shift_right_jam(value: u128, shift: u32) -> u128 {
+	match shift {
+		0 => value,
+		shift if shift >= 128 => (value != 0) as u128,
+		_ => (value >> shift) | ((value & ((1 << shift) - 1)) != 0) as u128
+	}
+}
+
+/// Adds two unpacked nonzero values and rounds the sum. Significands must
+/// be narrower than 108 bits.
+fn add_unpacked(format: &Format, a: Unpacked, b: Unpacked, rounding_mode: RoundingMode,
+	flags: &mut u8) -> u64 {
+	// Normalize so the most significant bits are at bit 125. It leaves at
+	// least 18 zero bits at the bottom so that shifting for alignment by up
+	// to 18 bits is exact, and the result has enough bits even if the
+	// subtraction cancels when shifting jams.
+	let normalize = |value: Unpacked| {
+		let shift = value.significand.leading_zeros() as i32 - 2;
+		Unpacked {
+			sign: value.sign,
+			exponent: value.exponent - shift,
+			significand: value.significand << shift
+		}
+	};
+	let a = normalize(a);
+	let b = normalize(b);
+	let (a, b) = match a.exponent >= b.exponent {
+		true => (a, b),
+		false => (b, a)
+	};
+	let b_significand = shift_right_jam(b.significand, (a.exponent - b.exponent) as u32);
+	let (sign, significand) = match a.sign == b.sign {
+		true => (a.sign, a.significand + b_significand),
+		false => match a.significand >= b_significand {
+			true => (a.sign, a.significand - b_significand),
+			false => (b.sign, b_significand - a.significand)
+		}
+	};
+	if significand == 0 {
+		// x - x is +0 except for rounding down
+		return format.zero(rounding_mode == RoundingMode::Down);
+	}
+	format.round_pack(sign, a.exponent, significand, rounding_mode, flags)
+}
+
+/// Returns `a` + `b`.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `b`
+/// * `rounding_mode`
+/// * `flags` Exception flags are accrued to
+pub fn add(format: &Format, a: u64, b: u64, rounding_mode: RoundingMode, flags: &mut u8) -> u64 {
+	if let Some(nan) = format.propagate_nan(&[a, b], flags) {
+		return nan;
+	}
+	let a_sign = format.get_sign(a);
+	let b_sign = format.get_sign(b);
+	match (format.is_infinite(a), format.is_infinite(b)) {
+		(true, true) => {
+			if a_sign != b_sign {
+				*flags |= FLAG_NV;
+				return format.canonical_nan();
+			}
+			return a;
+		},
+		(true, false) => return a,
+		(false, true) => return b,
+		(false, false) => {}
+	};
+	match (format.is_zero(a), format.is_zero(b)) {
+		(true, true) => match a_sign == b_sign {
+			true => a,
+			false => format.zero(rounding_mode == RoundingMode::Down)
+		},
+		(true, false) => b,
+		(false, true) => a,
+		(false, false) => add_unpacked(format, format.unpack(a), format.unpack(b), rounding_mode, flags)
+	}
+}
+
+/// Returns `a` - `b`.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `b`
+/// * `rounding_mode`
+/// * `flags` Exception flags are accrued to
+pub fn sub(format: &Format, a: u64, b: u64, rounding_mode: RoundingMode, flags: &mut u8) -> u64 {
+	add(format, a, format.negate(b), rounding_mode, flags)
+}
+
+/// Returns `a` * `b`.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `b`
+/// * `rounding_mode`
+/// * `flags` Exception flags are accrued to
+pub fn mul(format: &Format, a: u64, b: u64, rounding_mode: RoundingMode, flags: &mut u8) -> u64 {
+	if let Some(nan) = format.propagate_nan(&[a, b], flags) {
+		return nan;
+	}
+	let sign = format.get_sign(a) != format.get_sign(b);
+	if format.is_infinite(a) || format.is_infinite(b) {
+		if format.is_zero(a) || format.is_zero(b) {
+			*flags |= FLAG_NV;
+			return format.canonical_nan();
+		}
+		return format.infinity(sign);
+	}
+	let a = format.unpack(a);
+	let b = format.unpack(b);
+	format.round_pack(sign, a.exponent + b.exponent, a.significand * b.significand, rounding_mode, flags)
+}
+
+/// Returns `a` / `b`.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `b`
+/// * `rounding_mode`
+/// * `flags` Exception flags are accrued to
+pub fn div(format: &Format, a: u64, b: u64, rounding_mode: RoundingMode, flags: &mut u8) -> u64 {
+	if let Some(nan) = format.propagate_nan(&[a, b], flags) {
+		return nan;
+	}
+	let sign = format.get_sign(a) != format.get_sign(b);
+	match (format.is_infinite(a), format.is_infinite(b)) {
+		(true, true) => {
+			*flags |= FLAG_NV;
+			return format.canonical_nan();
+		},
+		(true, false) => return format.infinity(sign),
+		(false, true) => return format.zero(sign),
+		(false, false) => {}
+	};
+	match (format.is_zero(a), format.is_zero(b)) {
+		(true, true) => {
+			*flags |= FLAG_NV;
+			return format.canonical_nan();
+		},
+		(true, false) => return format.zero(sign),
+		(false, true) => {
+			*flags |= FLAG_DZ;
+			return format.infinity(sign);
+		},
+		(false, false) => {}
+	};
+	let a = format.unpack(a);
+	let b = format.unpack(b);
+	// Normalize to make the quotient have enough bits
+	let a_shift = a.significand.leading_zeros() - 1;
+	let b_shift = b.significand.leading_zeros() - 64;
+	let dividend = a.significand << a_shift;
+	let divisor = b.significand << b_shift;
+	let quotient = dividend / divisor;
+	let remainder = dividend % divisor;
+	let exponent = a.exponent - a_shift as i32 - b.exponent + b_shift as i32;
+	format.round_pack(sign, exponent, quotient | (remainder != 0) as u128, rounding_mode, flags)
+}
+
+/// Returns square root of `a`.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `rounding_mode`
+/// * `flags` Exception flags are accrued to
+pub fn sqrt(format: &Format, a: u64, rounding_mode: RoundingMode, flags: &mut u8) -> u64 {
+	if let Some(nan) = format.propagate_nan(&[a], flags) {
+		return nan;
+	}
+	if format.is_zero(a) {
+		return a;
+	}
+	if format.get_sign(a) {
+		*flags |= FLAG_NV;
+		return format.canonical_nan();
+	}
+	if format.is_infinite(a) {
+		return a;
+	}
+	let a = format.unpack(a);
+	// Normalize to make the root have enough bits and the exponent even
+	let mut shift = a.significand.leading_zeros() as i32 - 2;
+	if ((a.exponent - shift) & 1) != 0 {
+		shift -= 1;
+	}
+	let radicand = a.significand << shift;
+	let root = integer_sqrt(radicand);
+	let remainder = radicand - root * root;
+	format.round_pack(false, (a.exponent - shift) / 2, root | (remainder != 0) as u128, rounding_mode, flags)
+}
+
+/// Returns floor(sqrt(`value`)).
+fn integer_sqrt(value: u128) -> u128 {
+	let mut remainder = value;
+	let mut root = 0;
+	let mut bit = 1 << 126;
+	while bit > value {
+		bit >>= 2;
+	}
+	while bit != 0 {
+		if remainder >= root + bit {
+			remainder -= root + bit;
+			root = (root >> 1) + bit;
+		} else {
+			root >>= 1;
+		}
+		bit >>= 2;
+	}
+	root
+}
+
+/// Returns `a` * `b` + `c` with a single rounding.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `b`
+/// * `c`
+/// * `rounding_mode`
+/// * `flags` Exception flags are accrued to
+pub fn mul_add(format: &Format, a: u64, b: u64, c: u64, rounding_mode: RoundingMode, flags: &mut u8) -> u64 {
+	// Infinity * zero is invalid even if the addend is quiet NaN
+	if (format.is_infinite(a) && format.is_zero(b)) || (format.is_zero(a) && format.is_infinite(b)) {
+		*flags |= FLAG_NV;
+		return format.canonical_nan();
+	}
+	if let Some(nan) = format.propagate_nan(&[a, b, c], flags) {
+		return nan;
+	}
+	let product_sign = format.get_sign(a) != format.get_sign(b);
+	let c_sign = format.get_sign(c);
+	if format.is_infinite(a) || format.is_infinite(b) {
+		if format.is_infinite(c) && c_sign != product_sign {
+			*flags |= FLAG_NV;
+			return format.canonical_nan();
+		}
+		return format.infinity(product_sign);
+	}
+	if format.is_infinite(c) {
+		return c;
+	}
+	let product_is_zero = format.is_zero(a) || format.is_zero(b);
+	match (product_is_zero, format.is_zero(c)) {
+		(true, true) => match product_sign == c_sign {
+			true => c,
+			false => format.zero(rounding_mode == RoundingMode::Down)
+		},
+		(true, false) => c,
+		(false, _) => {
+			let a = format.unpack(a);
+			let b = format.unpack(b);
+			let product = Unpacked {
+				sign: product_sign,
+				exponent: a.exponent + b.exponent,
+				significand: a.significand * b.significand
+			};
+			match format.is_zero(c) {
+				true => format.round_pack(product.sign, product.exponent, product.significand, rounding_mode, flags),
+				false => add_unpacked(format, product, format.unpack(c), rounding_mode, flags)
+			}
+		}
+	}
+}
+
+/// Converts `a` in `from` format to `to` format.
+///
+/// # Arguments
+/// * `from`
+/// * `to`
+/// * `a`
+/// * `rounding_mode`
+/// * `flags` Exception flags are accrued to
+pub fn convert(from: &Format, to: &Format, a: u64, rounding_mode: RoundingMode, flags: &mut u8) -> u64 {
+	if from.propagate_nan(&[a], flags).is_some() {
+		return to.canonical_nan();
+	}
+	let sign = from.get_sign(a);
+	if from.is_infinite(a) {
+		return to.infinity(sign);
+	}
+	let a = from.unpack(a);
+	to.round_pack(sign, a.exponent, a.significand, rounding_mode, flags)
+}
+
+/// Converts signed or unsigned integer to floating-point value.
+///
+/// # Arguments
+/// * `format`
+/// * `value` Integer value. Signed value must be sign-extended to 64-bit.
+/// * `signed`
+/// * `rounding_mode`
+/// * `flags` Exception flags are accrued to
+pub fn from_integer(format: &Format, value: u64, signed: bool, rounding_mode: RoundingMode, flags: &mut u8) -> u64 {
+	let sign = signed && (value as i64) < 0;
+	let magnitude = match sign {
+		true => (value as i64).wrapping_neg() as u64,
+		false => value
+	};
+	format.round_pack(sign, 0, magnitude as u128, rounding_mode, flags)
+}
+
+/// Converts floating-point value to signed or unsigned integer. Out of range
+/// values and NaN raise invalid operation exception and are clamped.
+/// Returned value is sign-extended to 64-bit.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `width` Integer width in bits, 16, 32 or 64
+/// * `signed`
+/// * `rounding_mode`
+/// * `flags` Exception flags are accrued to
+pub fn to_integer(format: &Format, a: u64, width: u32, signed: bool, rounding_mode: RoundingMode, flags: &mut u8) -> u64 {
+	let (min, max) = match signed {
+		true => (-(1i128 << (width - 1)), (1i128 << (width - 1)) - 1),
+		false => (0, (1i128 << width) - 1)
+	};
+	let sign_extend = |value: i128| match width {
+		32 => value as i32 as i64 as u64,
+		_ => value as u64
+	};
+	if format.is_nan(a) {
+		*flags |= FLAG_NV;
+		return sign_extend(max);
+	}
+	let sign = format.get_sign(a);
+	if format.is_infinite(a) {
+		*flags |= FLAG_NV;
+		return match sign {
+			true => sign_extend(min),
+			false => sign_extend(max)
+		};
+	}
+	let a = format.unpack(a);
+	// Values not less than 2^65 are out of range anyway
+	let (magnitude, inexact) = match a.exponent > 64 {
+		true => (1 << 65, false),
+		false => {
+			let significand = match a.exponent > 0 {
+				true => a.significand << a.exponent,
+				false => a.significand
+			};
+			round_significand(sign, a.exponent.min(0), significand, 0, rounding_mode)
+		}
+	};
+	let value = match sign {
+		true => -(magnitude.min(1 << 65) as i128),
+		false => magnitude.min(1 << 65) as i128
+	};
+	if value < min {
+		*flags |= FLAG_NV;
+		return sign_extend(min);
+	}
+	if value > max {
+		*flags |= FLAG_NV;
+		return sign_extend(max);
+	}
+	if inexact {
+		*flags |= FLAG_NX;
+	}
+	sign_extend(value)
+}
+
+/// Returns whether `a` == `b`. Raises invalid operation exception only for
+/// signaling NaN.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `b`
+/// * `flags` Exception flags are accrued to
+pub fn eq(format: &Format, a: u64, b: u64, flags: &mut u8) -> bool {
+	if format.propagate_nan(&[a, b], flags).is_some() {
+		return false;
+	}
+	a == b || (format.is_zero(a) && format.is_zero(b))
+}
+
+/// Returns whether `a` < `b`. Raises invalid operation exception for any NaN.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `b`
+/// * `flags` Exception flags are accrued to
+pub fn lt(format: &Format, a: u64, b: u64, flags: &mut u8) -> bool {
+	if format.is_nan(a) || format.is_nan(b) {
+		*flags |= FLAG_NV;
+		return false;
+	}
+	get_order(format, a) < get_order(format, b)
+}
+
+/// Returns whether `a` <= `b`. Raises invalid operation exception for any NaN.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `b`
+/// * `flags` Exception flags are accrued to
+pub fn le(format: &Format, a: u64, b: u64, flags: &mut u8) -> bool {
+	if format.is_nan(a) || format.is_nan(b) {
+		*flags |= FLAG_NV;
+		return false;
+	}
+	get_order(format, a) <= get_order(format, b)
+}
+
+/// Maps non-NaN value to integer having the same order. Both zeros are 0.
+fn get_order(format: &Format, value: u64) -> i64 {
+	let magnitude = (value & (format.sign_bit() - 1)) as i64;
+	match format.get_sign(value) {
+		true => -magnitude,
+		false => magnitude
+	}
+}
+
+/// Returns the smaller of `a` and `b`. -0 is smaller than +0. If either
+/// operand is NaN, returns the other. If both are NaN, returns canonical NaN.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `b`
+/// * `flags` Exception flags are accrued to
+pub fn min(format: &Format, a: u64, b: u64, flags: &mut u8) -> u64 {
+	min_max(format, a, b, true, flags)
+}
+
+/// Returns the larger of `a` and `b`. +0 is larger than -0. If either
+/// operand is NaN, returns the other. If both are NaN, returns canonical NaN.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `b`
+/// * `flags` Exception flags are accrued to
+pub fn max(format: &Format, a: u64, b: u64, flags: &mut u8) -> u64 {
+	min_max(format, a, b, false, flags)
+}
+
+fn min_max(format: &Format, a: u64, b: u64, is_min: bool, flags: &mut u8) -> u64 {
+	if format.is_signaling_nan(a) || format.is_signaling_nan(b) {
+		*flags |= FLAG_NV;
+	}
+	match (format.is_nan(a), format.is_nan(b)) {
+		(true, true) => return format.canonical_nan(),
+		(true, false) => return b,
+		(false, true) => return a,
+		(false, false) => {}
+	};
+	// Compares including the sign of zero
+	let a_order = match format.get_sign(a) {
+		true => -((a & (format.sign_bit() - 1)) as i64) - 1,
+		false => a as i64
+	};
+	let b_order = match format.get_sign(b) {
+		true => -((b & (format.sign_bit() - 1)) as i64) - 1,
+		false => b as i64
+	};
+	match (a_order < b_order) == is_min {
+		true => a,
+		false => b
+	}
+}
+
+/// `reciprocal_estimate()` lookup table indexed by the upper 7 bits of the
+/// fraction. Each entry is the upper 7 bits of the output fraction.
+const RECIPROCAL_TABLE: [u8; 128] = [
+	127, 125, 123, 121, 119, 117, 116, 114, 112, 110, 109, 107, 105, 104, 102, 100,
+	99, 97, 96, 94, 93, 91, 90, 88, 87, 85, 84, 83, 81, 80, 79, 77,
+	76, 75, 74, 72, 71, 70, 69, 68, 66, 65, 64, 63, 62, 61, 60, 59,
+	58, 57, 56, 55, 54, 53, 52, 51, 50, 49, 48, 47, 46, 45, 44, 43,
+	42, 41, 40, 40, 39, 38, 37, 36, 35, 35, 34, 33, 32, 31, 31, 30,
+	29, 28, 28, 27, 26, 25, 25, 24, 23, 23, 22, 21, 21, 20, 19, 19,
+	18, 17, 17, 16, 15, 15, 14, 14, 13, 12, 12, 11, 11, 10, 9, 9,
+	8, 8, 7, 7, 6, 5, 5, 4, 4, 3, 3, 2, 2, 1, 1, 0
+];
+
+/// `reciprocal_sqrt_estimate()` lookup table indexed by the least significant
+/// bit of the exponent and the upper 6 bits of the fraction. Each entry is
+/// the upper 7 bits of the output fraction.
+const RECIPROCAL_SQRT_TABLE: [u8; 128] = [
+	52, 51, 50, 48, 47, 46, 44, 43, 42, 41, 40, 39, 38, 36, 35, 34,
+	33, 32, 31, 30, 30, 29, 28, 27, 26, 25, 24, 23, 23, 22, 21, 20,
+	19, 19, 18, 17, 16, 16, 15, 14, 14, 13, 12, 12, 11, 10, 10, 9,
+	9, 8, 7, 7, 6, 6, 5, 4, 4, 3, 3, 2, 2, 1, 1, 0,
+	127, 125, 123, 121, 119, 118, 116, 114, 113, 111, 109, 108, 106, 105, 103, 102,
+	100, 99, 97, 96, 95, 93, 92, 91, 90, 88, 87, 86, 85, 84, 83, 82,
+	80, 79, 78, 77, 76, 75, 74, 73, 72, 71, 70, 70, 69, 68, 67, 66,
+	65, 64, 63, 63, 62, 61, 60, 59, 59, 58, 57, 56, 56, 55, 54, 53
+];
+
+/// Returns the biased exponent and the fraction of finite nonzero value.
+/// Subnormal value is normalized, its exponent is zero or negative and the
+/// leading one is removed from the fraction.
+fn normalize_fraction(format: &Format, value: u64) -> (i64, u64) {
+	let mut exponent = format.get_exponent(value) as i64;
+	let mut fraction = format.get_fraction(value);
+	if exponent == 0 {
+		while ((fraction >> (format.fraction_width - 1)) & 1) == 0 {
+			exponent -= 1;
+			fraction <<= 1;
+		}
+		fraction = (fraction << 1) & format.fraction_mask();
+	}
+	(exponent, fraction)
+}
+
+/// Returns the estimate of 1 / `a` accurate to 7 bits, used by the vector
+/// `VFREC7.V` instruction. The rounding mode matters only when a small
+/// subnormal input overflows.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `rounding_mode`
+/// * `flags` Exception flags are accrued to
+pub fn reciprocal_estimate(format: &Format, a: u64, rounding_mode: RoundingMode, flags: &mut u8) -> u64 {
+	if let Some(nan) = format.propagate_nan(&[a], flags) {
+		return nan;
+	}
+	let sign = format.get_sign(a);
+	if format.is_infinite(a) {
+		return format.zero(sign);
+	}
+	if format.is_zero(a) {
+		*flags |= FLAG_DZ;
+		return format.infinity(sign);
+	}
+	let (exponent, fraction) = normalize_fraction(format, a);
+	if exponent < -1 {
+		*flags |= FLAG_OF | FLAG_NX;
+		let to_infinity = match rounding_mode {
+			RoundingMode::NearestEven | RoundingMode::NearestMaxMagnitude => true,
+			RoundingMode::TowardZero => false,
+			RoundingMode::Down => sign,
+			RoundingMode::Up => !sign
+		};
+		return match to_infinity {
+			true => format.infinity(sign),
+			false => format.max_finite(sign)
+		};
+	}
+	let width = format.fraction_width;
+	let mut out_fraction = (RECIPROCAL_TABLE[(fraction >> (width - 7)) as usize] as u64) << (width - 7);
+	let mut out_exponent = 2 * format.bias() as i64 - 1 - exponent;
+	// The result is subnormal if the input is large
+	if out_exponent <= 0 {
+		out_fraction = (out_fraction >> 1) | (1 << (width - 1));
+		if out_exponent == -1 {
+			out_fraction >>= 1;
+			out_exponent = 0;
+		}
+	}
+	format.pack_sign(sign) | ((out_exponent as u64) << width) | out_fraction
+}
+
+/// Returns the estimate of 1 / sqrt(`a`) accurate to 7 bits, used by the
+/// vector `VFRSQRT7.V` instruction.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+/// * `flags` Exception flags are accrued to
+pub fn reciprocal_sqrt_estimate(format: &Format, a: u64, flags: &mut u8) -> u64 {
+	if let Some(nan) = format.propagate_nan(&[a], flags) {
+		return nan;
+	}
+	let sign = format.get_sign(a);
+	if format.is_zero(a) {
+		*flags |= FLAG_DZ;
+		return format.infinity(sign);
+	}
+	if sign {
+		*flags |= FLAG_NV;
+		return format.canonical_nan();
+	}
+	if format.is_infinite(a) {
+		return format.zero(false);
+	}
+	let (exponent, fraction) = normalize_fraction(format, a);
+	let width = format.fraction_width;
+	let index = (((exponent & 1) as u64) << 6) | (fraction >> (width - 6));
+	let out_fraction = (RECIPROCAL_SQRT_TABLE[index as usize] as u64) << (width - 7);
+	let out_exponent = (3 * format.bias() as i64 - 1 - exponent) / 2;
+	((out_exponent as u64) << width) | out_fraction
+}
+
+/// Returns the class of `a` as a mask used by FCLASS instructions.
+///
+/// # Arguments
+/// * `format`
+/// * `a`
+pub fn classify(format: &Format, a: u64) -> u64 {
+	let sign = format.get_sign(a);
+	let exponent = format.get_exponent(a);
+	if format.is_infinite(a) {
+		return match sign {
+			true => 1 << 0,
+			false => 1 << 7
+		};
+	}
+	if format.is_nan(a) {
+		return match format.is_signaling_nan(a) {
+			true => 1 << 8,
+			false => 1 << 9
+		};
+	}
+	if format.is_zero(a) {
+		return match sign {
+			true => 1 << 3,
+			false => 1 << 4
+		};
+	}
+	match (exponent == 0, sign) {
+		(true, true) => 1 << 2,
+		(true, false) => 1 << 5,
+		(false, true) => 1 << 1,
+		(false, false) => 1 << 6
+	}
+}
+
+#[cfg(test)]
+mod test_fpu {
+	use super::*;
+
+	fn s(value: f32) -> u64 {
+		value.to_bits() as u64
+	}
+
+	fn d(value: f64) -> u64 {
+		value.to_bits()
+	}
+
+	const RNE: RoundingMode = RoundingMode::NearestEven;
+
+	#[test]
+	fn add_single() {
+		let mut flags = 0;
+		assert_eq!(s(3.5), add(&SINGLE, s(2.5), s(1.0), RNE, &mut flags));
+		assert_eq!(0, flags);
+		assert_eq!(s(-1234.0), add(&SINGLE, s(-1235.1), s(1.1), RNE, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+		flags = 0;
+		assert_eq!(s(std::f32::consts::PI), add(&SINGLE, s(std::f32::consts::PI), s(0.00000001), RNE, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+		flags = 0;
+		let infinity = SINGLE.infinity(false);
+		let negative_infinity = SINGLE.infinity(true);
+		assert_eq!(SINGLE.canonical_nan(), add(&SINGLE, infinity, negative_infinity, RNE, &mut flags));
+		assert_eq!(FLAG_NV, flags);
+		flags = 0;
+		// x - x is -0 only when rounding down
+		assert_eq!(s(0.0), sub(&SINGLE, s(1.5), s(1.5), RNE, &mut flags));
+		assert_eq!(s(-0.0), sub(&SINGLE, s(1.5), s(1.5), RoundingMode::Down, &mut flags));
+		assert_eq!(0, flags);
+	}
+
+	#[test]
+	fn rounding_mode() {
+		let mut flags = 0;
+		// 1 + 2^-24 is the halfway between 1 and the next value
+		let a = s(1.0);
+		let b = s(1.0 / 16777216.0);
+		let next = s(1.0) + 1;
+		assert_eq!(a, add(&SINGLE, a, b, RoundingMode::NearestEven, &mut flags));
+		assert_eq!(next, add(&SINGLE, a, b, RoundingMode::NearestMaxMagnitude, &mut flags));
+		assert_eq!(a, add(&SINGLE, a, b, RoundingMode::TowardZero, &mut flags));
+		assert_eq!(a, add(&SINGLE, a, b, RoundingMode::Down, &mut flags));
+		assert_eq!(next, add(&SINGLE, a, b, RoundingMode::Up, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+	}
+
+	#[test]
+	fn overflow_and_underflow() {
+		let mut flags = 0;
+		let max = SINGLE.max_finite(false);
+		assert_eq!(SINGLE.infinity(false), mul(&SINGLE, max, s(2.0), RNE, &mut flags));
+		assert_eq!(FLAG_OF | FLAG_NX, flags);
+		flags = 0;
+		assert_eq!(max, mul(&SINGLE, max, s(2.0), RoundingMode::TowardZero, &mut flags));
+		assert_eq!(FLAG_OF | FLAG_NX, flags);
+		flags = 0;
+		// Exact subnormal result doesn't underflow
+		assert_eq!(1, mul(&SINGLE, 2, s(0.5), RNE, &mut flags));
+		assert_eq!(0, flags);
+		assert_eq!(0, mul(&SINGLE, 1, s(0.5), RNE, &mut flags));
+		assert_eq!(FLAG_UF | FLAG_NX, flags);
+		flags = 0;
+		// Rounded up to the minimum normal value is not tiny after rounding
+		// if the value rounded with unbounded exponent range is also normal
+		let min_normal = 0x800000;
+		let below = d(2.0f64.powi(-126) * (1.0 - 2.0f64.powi(-26)));
+		assert_eq!(min_normal, convert(&DOUBLE, &SINGLE, below, RNE, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+		flags = 0;
+		let below = d(2.0f64.powi(-126) * (1.0 - 2.0f64.powi(-24)));
+		assert_eq!(min_normal, convert(&DOUBLE, &SINGLE, below, RNE, &mut flags));
+		assert_eq!(FLAG_UF | FLAG_NX, flags);
+	}
+
+	#[test]
+	fn div_and_sqrt() {
+		let mut flags = 0;
+		assert_eq!(s(1.1557274), div(&SINGLE, s(std::f32::consts::PI), s(std::f32::consts::E), RNE, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+		flags = 0;
+		assert_eq!(SINGLE.infinity(true), div(&SINGLE, s(-1.0), s(0.0), RNE, &mut flags));
+		assert_eq!(FLAG_DZ, flags);
+		flags = 0;
+		assert_eq!(s(1.7724539), sqrt(&SINGLE, s(std::f32::consts::PI), RNE, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+		flags = 0;
+		assert_eq!(s(100.0), sqrt(&SINGLE, s(10000.0), RNE, &mut flags));
+		assert_eq!(0, flags);
+		assert_eq!(SINGLE.canonical_nan(), sqrt(&SINGLE, s(-1.0), RNE, &mut flags));
+		assert_eq!(FLAG_NV, flags);
+		flags = 0;
+		assert_eq!(d(1.7724538509055159), sqrt(&DOUBLE, d(std::f64::consts::PI), RNE, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+	}
+
+	#[test]
+	fn mul_add_single() {
+		let mut flags = 0;
+		assert_eq!(s(3.5), mul_add(&SINGLE, s(1.0), s(2.5), s(1.0), RNE, &mut flags));
+		assert_eq!(0, flags);
+		assert_eq!(s(1236.2), mul_add(&SINGLE, s(-1.0), s(-1235.1), s(1.1), RNE, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+		flags = 0;
+		// Single rounding: (1 + 2^-23) * (1 - 2^-23) - 1 = -2^-46
+		let a = s(1.0) + 1;
+		let b = s(1.0 - 1.0 / 8388608.0);
+		assert_eq!(s(-1.0 / 70368744177664.0), mul_add(&SINGLE, a, b, s(-1.0), RNE, &mut flags));
+		assert_eq!(0, flags);
+		assert_eq!(SINGLE.canonical_nan(),
+			mul_add(&SINGLE, SINGLE.infinity(false), s(0.0), SINGLE.canonical_nan(), RNE, &mut flags));
+		assert_eq!(FLAG_NV, flags);
+	}
+
+	#[test]
+	fn integer_conversion() {
+		let mut flags = 0;
+		assert_eq!(-1i64 as u64, to_integer(&SINGLE, s(-1.1), 32, true, RoundingMode::TowardZero, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+		flags = 0;
+		assert_eq!(0, to_integer(&SINGLE, s(-0.9), 32, false, RoundingMode::TowardZero, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+		flags = 0;
+		assert_eq!(0, to_integer(&SINGLE, s(-1.0), 32, false, RoundingMode::TowardZero, &mut flags));
+		assert_eq!(FLAG_NV, flags);
+		flags = 0;
+		assert_eq!(0x7fffffff, to_integer(&SINGLE, s(3e9), 32, true, RNE, &mut flags));
+		assert_eq!(FLAG_NV, flags);
+		flags = 0;
+		assert_eq!(0xffffffff80000000, to_integer(&SINGLE, s(-3e9), 32, true, RNE, &mut flags));
+		assert_eq!(0xffffffffffffffff, to_integer(&SINGLE, s(5e9), 32, false, RNE, &mut flags));
+		assert_eq!(0x7fffffffffffffff, to_integer(&SINGLE, SINGLE.canonical_nan(), 64, true, RNE, &mut flags));
+		assert_eq!(3, to_integer(&DOUBLE, d(2.5), 64, true, RoundingMode::NearestMaxMagnitude, &mut flags));
+		assert_eq!(2, to_integer(&DOUBLE, d(2.5), 64, true, RNE, &mut flags));
+		assert_eq!(0x8000000000000000, to_integer(&DOUBLE, d(-9223372036854775808.0), 64, true, RNE, &mut flags));
+		flags = 0;
+		assert_eq!(s(-2.0), from_integer(&SINGLE, -2i64 as u64, true, RNE, &mut flags));
+		assert_eq!(0, flags);
+		assert_eq!(s(4294967296.0), from_integer(&SINGLE, 0xffffffff, false, RNE, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+	}
+
+	#[test]
+	fn compare_and_min_max() {
+		let mut flags = 0;
+		assert!(eq(&SINGLE, s(0.0), s(-0.0), &mut flags));
+		assert!(!lt(&SINGLE, s(-0.0), s(0.0), &mut flags));
+		assert!(lt(&SINGLE, s(-2.0), s(-1.0), &mut flags));
+		assert!(le(&SINGLE, s(-1.0), s(-1.0), &mut flags));
+		assert_eq!(0, flags);
+		assert!(!eq(&SINGLE, SINGLE.canonical_nan(), s(1.0), &mut flags));
+		assert_eq!(0, flags);
+		assert!(!lt(&SINGLE, SINGLE.canonical_nan(), s(1.0), &mut flags));
+		assert_eq!(FLAG_NV, flags);
+		flags = 0;
+		assert_eq!(s(-0.0), min(&SINGLE, s(0.0), s(-0.0), &mut flags));
+		assert_eq!(s(0.0), max(&SINGLE, s(-0.0), s(0.0), &mut flags));
+		assert_eq!(s(1.0), min(&SINGLE, SINGLE.canonical_nan(), s(1.0), &mut flags));
+		assert_eq!(0, flags);
+		assert_eq!(s(1.0), max(&SINGLE, 0x7f800001, s(1.0), &mut flags));
+		assert_eq!(FLAG_NV, flags);
+	}
+
+	#[test]
+	fn classify_single() {
+		assert_eq!(1 << 0, classify(&SINGLE, SINGLE.infinity(true)));
+		assert_eq!(1 << 1, classify(&SINGLE, s(-1.0)));
+		assert_eq!(1 << 2, classify(&SINGLE, 0x807fffff));
+		assert_eq!(1 << 3, classify(&SINGLE, s(-0.0)));
+		assert_eq!(1 << 4, classify(&SINGLE, s(0.0)));
+		assert_eq!(1 << 5, classify(&SINGLE, 0x007fffff));
+		assert_eq!(1 << 6, classify(&SINGLE, s(1.0)));
+		assert_eq!(1 << 7, classify(&SINGLE, SINGLE.infinity(false)));
+		assert_eq!(1 << 8, classify(&SINGLE, 0x7f800001));
+		assert_eq!(1 << 9, classify(&SINGLE, 0x7fc00000));
+	}
+
+	#[test]
+	fn convert_format() {
+		let mut flags = 0;
+		assert_eq!(d(1.5), convert(&SINGLE, &DOUBLE, s(1.5), RNE, &mut flags));
+		assert_eq!(0, flags);
+		assert_eq!(s(0.1), convert(&DOUBLE, &SINGLE, d(0.1), RNE, &mut flags));
+		assert_eq!(FLAG_NX, flags);
+		flags = 0;
+		assert_eq!(DOUBLE.canonical_nan(), convert(&SINGLE, &DOUBLE, 0x7f800001, RNE, &mut flags));
+		assert_eq!(FLAG_NV, flags);
+	}
+
+	#[test]
+	fn estimate() {
+		let mut flags = 0;
+		assert_eq!(s(0.99609375), reciprocal_estimate(&SINGLE, s(1.0), RNE, &mut flags));
+		assert_eq!(d(-0.2490234375), reciprocal_estimate(&DOUBLE, d(-4.0), RNE, &mut flags));
+		assert_eq!(s(255.0 / 512.0), reciprocal_sqrt_estimate(&SINGLE, s(4.0), &mut flags));
+		assert_eq!(d(0.703125), reciprocal_sqrt_estimate(&DOUBLE, d(2.0), &mut flags));
+		assert_eq!(0, flags);
+		// The reciprocal of the largest value is subnormal
+		assert_eq!(0x00200000, reciprocal_estimate(&SINGLE, SINGLE.max_finite(false), RNE, &mut flags));
+		// The reciprocal of the smallest subnormal overflows
+		assert_eq!(SINGLE.max_finite(false), reciprocal_estimate(&SINGLE, 1, RoundingMode::TowardZero, &mut flags));
+		assert_eq!(FLAG_OF | FLAG_NX, flags);
+		flags = 0;
+		assert_eq!(SINGLE.infinity(true), reciprocal_estimate(&SINGLE, s(-0.0), RNE, &mut flags));
+		assert_eq!(FLAG_DZ, flags);
+		flags = 0;
+		assert_eq!(SINGLE.canonical_nan(), reciprocal_sqrt_estimate(&SINGLE, s(-1.0), &mut flags));
+		assert_eq!(FLAG_NV, flags);
+		flags = 0;
+		assert_eq!(s(0.0), reciprocal_sqrt_estimate(&SINGLE, SINGLE.infinity(false), &mut flags));
+		assert_eq!(0, flags);
+	}
+
+	// Compares with the host floating-point arithmetic in round to nearest even
+	#[test]
+	fn same_as_host() {
+		let mut value: u64 = 0x123456789abcdef;
+		let mut next = || {
+			value ^= value << 13;
+			value ^= value >> 7;
+			value ^= value << 17;
+			value
+		};
+		for _i in 0..100000 {
+			let a = next();
+			let b = next();
+			let c = next();
+			let mut flags = 0;
+			let (fa, fb, fc) = (f64::from_bits(a), f64::from_bits(b), f64::from_bits(c));
+			let check = |expected: f64, actual: u64| {
+				match expected.is_nan() {
+					true => assert!(DOUBLE.is_nan(actual)),
+					false => assert_eq!(expected.to_bits(), actual, "{:x} {:x} {:x}", a, b, c)
+				};
+			};
+			check(fa + fb, add(&DOUBLE, a, b, RNE, &mut flags));
+			check(fa - fb, sub(&DOUBLE, a, b, RNE, &mut flags));
+			check(fa * fb, mul(&DOUBLE, a, b, RNE, &mut flags));
+			check(fa / fb, div(&DOUBLE, a, b, RNE, &mut flags));
+			check(fa.abs().sqrt(), sqrt(&DOUBLE, a & !DOUBLE.sign_bit(), RNE, &mut flags));
+			check(fa.mul_add(fb, fc), mul_add(&DOUBLE, a, b, c, RNE, &mut flags));
+			let (sa, sb) = (f32::from_bits(a as u32), f32::from_bits(b as u32));
+			let check_single = |expected: f32, actual: u64| {
+				match expected.is_nan() {
+					true => assert!(SINGLE.is_nan(actual)),
+					false => assert_eq!(expected.to_bits() as u64, actual, "{:x} {:x}", a as u32, b as u32)
+				};
+			};
+			check_single(sa + sb, add(&SINGLE, a as u32 as u64, b as u32 as u64, RNE, &mut flags));
+			check_single(sa * sb, mul(&SINGLE, a as u32 as u64, b as u32 as u64, RNE, &mut flags));
+			check_single(sa / sb, div(&SINGLE, a as u32 as u64, b as u32 as u64, RNE, &mut flags));
+			check_single(fa as f32, convert(&DOUBLE, &SINGLE, a, RNE, &mut flags));
+		}
+	}
+}
@@ -0,0 +1,381 @@
+use cpu::PrivilegeMode;
+
+/// The number of counters. `mcycle`, `minstret`, and `mhpmcounter3`-`mhpmcounter31`
+/// are indexed by the lower five bits of their CSR numbers. Index 1 is
+/// `time` which is memory-mapped `mtime` in `Clint`.
+pub const HPM_COUNTER_NUM: usize = 32;
+
+const HPM_COUNTER_CYCLE: usize = 0;
+const HPM_COUNTER_INSTRET: usize = 2;
+
+/// The first programmable counter, `mhpmcounter3`
+const HPM_COUNTER_FIRST_PROGRAMMABLE: usize = 3;
+
+/// `mhpmevent` value which counts nothing
+pub const HPM_EVENT_NONE: u64 = 0;
+
+/// `mhpmevent` value which counts retired load instructions, including
+/// floating-point, vector, LR, and AMO instructions
+pub const HPM_EVENT_LOAD: u64 = 1;
+
+/// `mhpmevent` value which counts retired store instructions, including
+/// floating-point, vector, SC, and AMO instructions
+pub const HPM_EVENT_STORE: u64 = 2;
+
+/// `mhpmevent` value which counts retired conditional branch instructions
+pub const HPM_EVENT_BRANCH: u64 = 3;
+
+/// `mhpmevent` value which counts taken exceptions and interrupts
+pub const HPM_EVENT_TRAP: u64 = 4;
+
+/// `mhpmevent` value which counts instruction fetch address translations
+/// missing TLB
+pub const HPM_EVENT_ITLB_MISS: u64 = 5;
+
+/// `mhpmevent` value which counts load and store address translations
+/// missing TLB
+pub const HPM_EVENT_DTLB_MISS: u64 = 6;
+
+const HPM_EVENT_NUM: usize = 7;
+
+/// Overflow bit of `mhpmevent`. Set when the counter overflows, and the
+/// local counter-overflow interrupt is raised unless it's already set.
+pub const HPM_EVENT_OF: u64 = 0x8000000000000000;
+
+/// Mode inhibit bits of `mhpmevent`. The counter doesn't count events
+/// in machine, supervisor, user, virtual supervisor, and virtual user mode
+/// respectively if set.
+pub const HPM_EVENT_MINH: u64 = 0x4000000000000000;
+pub const HPM_EVENT_SINH: u64 = 0x2000000000000000;
+pub const HPM_EVENT_UINH: u64 = 0x1000000000000000;
+pub const HPM_EVENT_VSINH: u64 = 0x0800000000000000;
+pub const HPM_EVENT_VUINH: u64 = 0x0400000000000000;
+
+const HPM_EVENT_FILTER_MASK: u64 = HPM_EVENT_OF | HPM_EVENT_MINH | HPM_EVENT_SINH |
+	HPM_EVENT_UINH | HPM_EVENT_VSINH | HPM_EVENT_VUINH;
+
+// Indices of modes for mode inhibit bits
+const HPM_MODE_MACHINE: usize = 0;
+const HPM_MODE_SUPERVISOR: usize = 1;
+const HPM_MODE_USER: usize = 2;
+const HPM_MODE_VIRTUAL_SUPERVISOR: usize = 3;
+const HPM_MODE_VIRTUAL_USER: usize = 4;
+const HPM_MODE_NUM: usize = 5;
+
+/// Emulates hardware performance monitor. Holds `mcycle`, `minstret`,
+/// `mhpmcounter3`-`mhpmcounter31`, `mhpmevent3`-`mhpmevent31`, and
+/// `mcountinhibit` CSRs. `Cpu` reports cycles, retired instructions,
+/// and events to it. The unprivileged counter CSRs are read-only shadows
+/// of them.
+///
+/// `mhpmevent` has the overflow and mode inhibit bits of Sscofpmf extension.
+/// `Cpu` polls the overflow with `take_overflow()` to raise local
+/// counter-overflow interrupt.
+pub struct Hpm {
+	counters: [u64; HPM_COUNTER_NUM],
+	events: [u64; HPM_COUNTER_NUM],
+	inhibit: u32,
+
+	/// Counters written by the current instruction. The write takes effect
+	/// after the instruction, so they don't count for it.
+	written: u32,
+
+	/// Bitmaps of the counters counting each event. Cached for fast path.
+	event_counters: [u32; HPM_EVENT_NUM],
+
+	/// Bitmaps of the counters inhibited in each mode. Cached for fast path.
+	mode_inhibited_counters: [u32; HPM_MODE_NUM],
+
+	/// The current mode, `HPM_MODE_*`
+	mode: usize,
+
+	/// Whether any counter overflowed without overflow bit since the last
+	/// `take_overflow()` call
+	overflowed: bool,
+
+	/// Whether any programmable counter counts event. Cached for fast path.
+	active: bool
+}
+
+impl Default for Hpm {
+	fn default() -> Self {
+		Hpm::new()
+	}
+}
+
+impl Hpm {
+	/// Creates a new `Hpm`. All counters are zero and count no event.
+	pub fn new() -> Self {
+		Hpm {
+			counters: [0; HPM_COUNTER_NUM],
+			events: [HPM_EVENT_NONE; HPM_COUNTER_NUM],
+			inhibit: 0,
+			written: 0,
+			event_counters: [0; HPM_EVENT_NUM],
+			mode_inhibited_counters: [0; HPM_MODE_NUM],
+			mode: HPM_MODE_MACHINE,
+			overflowed: false,
+			active: false
+		}
+	}
+
+	/// Reads counter.
+	///
+	/// # Arguments
+	/// * `index` Counter index, the lower five bits of CSR number
+	pub fn read_counter(&self, index: usize) -> u64 {
+		self.counters[index]
+	}
+
+	/// Writes counter. The counter doesn't count the current instruction.
+	///
+	/// # Arguments
+	/// * `index` Counter index, the lower five bits of CSR number
+	/// * `value`
+	pub fn write_counter(&mut self, index: usize, value: u64) {
+		self.counters[index] = value;
+		self.written |= 1 << index;
+	}
+
+	/// Reads `mhpmevent` CSR.
+	///
+	/// # Arguments
+	/// * `index` Counter index, 3-31
+	pub fn read_event(&self, index: usize) -> u64 {
+		self.events[index]
+	}
+
+	/// Writes `mhpmevent` CSR. It's WARL, unsupported event is written
+	/// as `HPM_EVENT_NONE`. The overflow and mode inhibit bits are kept.
+	///
+	/// # Arguments
+	/// * `index` Counter index, 3-31
+	/// * `value`
+	pub fn write_event(&mut self, index: usize, value: u64) {
+		let event = value & !HPM_EVENT_FILTER_MASK;
+		self.events[index] = (value & HPM_EVENT_FILTER_MASK) | match event < HPM_EVENT_NUM as u64 {
+			true => event,
+			false => HPM_EVENT_NONE
+		};
+		self.update_event_counters();
+	}
+
+	/// Returns the bitmap of the counters whose overflow bit is set, for
+	/// `scountovf` CSR.
+	pub fn read_overflows(&self) -> u64 {
+		let mut overflows = 0;
+		for index in HPM_COUNTER_FIRST_PROGRAMMABLE..HPM_COUNTER_NUM {
+			if (self.events[index] & HPM_EVENT_OF) != 0 {
+				overflows |= 1 << index;
+			}
+		}
+		overflows
+	}
+
+	/// Returns whether any counter overflowed without overflow bit since
+	/// the last call, and resets it. `Cpu` raises local counter-overflow
+	/// interrupt if so.
+	pub fn take_overflow(&mut self) -> bool {
+		let overflowed = self.overflowed;
+		self.overflowed = false;
+		overflowed
+	}
+
+	/// Updates the current mode for mode inhibit bits. `Cpu` needs to call
+	/// this method whenever the privilege mode or virtualization mode is updated.
+	///
+	/// # Arguments
+	/// * `mode`
+	/// * `virtualization_mode`
+	pub fn update_privilege_mode(&mut self, mode: &PrivilegeMode, virtualization_mode: bool) {
+		self.mode = match (mode, virtualization_mode) {
+			(PrivilegeMode::Supervisor, true) => HPM_MODE_VIRTUAL_SUPERVISOR,
+			(PrivilegeMode::User, true) => HPM_MODE_VIRTUAL_USER,
+			(PrivilegeMode::Supervisor, false) => HPM_MODE_SUPERVISOR,
+			(PrivilegeMode::User, false) => HPM_MODE_USER,
+			_ => HPM_MODE_MACHINE
+		};
+	}
+
+	/// Reads `mcountinhibit` CSR.
+	pub fn read_inhibit(&self) -> u64 {
+		self.inhibit as u64
+	}
+
+	/// Writes `mcountinhibit` CSR. `TM` bit is hardwired to zero because
+	/// `time` can't be inhibited.
+	///
+	/// # Arguments
+	/// * `value`
+	pub fn write_inhibit(&mut self, value: u64) {
+		self.inhibit = (value as u32) & !(1 << 1);
+		self.update_event_counters();
+	}
+
+	fn update_event_counters(&mut self) {
+		self.event_counters = [0; HPM_EVENT_NUM];
+		self.mode_inhibited_counters = [0; HPM_MODE_NUM];
+		let mode_inhibit_bits = [HPM_EVENT_MINH, HPM_EVENT_SINH, HPM_EVENT_UINH, HPM_EVENT_VSINH, HPM_EVENT_VUINH];
+		for index in HPM_COUNTER_FIRST_PROGRAMMABLE..HPM_COUNTER_NUM {
+			let event = self.events[index];
+			if ((self.inhibit >> index) & 1) == 0 {
+				self.event_counters[(event & !HPM_EVENT_FILTER_MASK) as usize] |= 1 << index;
+			}
+			for (mode, bit) in mode_inhibit_bits.iter().enumerate() {
+				if (event & bit) != 0 {
+					self.mode_inhibited_counters[mode] |= 1 << index;
+				}
+			}
+		}
+		// HPM_EVENT_NONE counts nothing
+		self.event_counters[HPM_EVENT_NONE as usize] = 0;
+		self.active = self.event_counters.iter().any(|counters| *counters != 0);
+	}
+
+	/// Returns whether any programmable counter counts event. `Cpu` can
+	/// skip classifying instructions if not.
+	pub fn is_counting_events(&self) -> bool {
+		self.active
+	}
+
+	/// Counts event in the counters selecting it unless they are inhibited
+	/// in the current mode. The overflowing counter sets its overflow bit.
+	///
+	/// # Arguments
+	/// * `event` `HPM_EVENT_*`
+	/// * `num` The number of events
+	pub fn count_event(&mut self, event: u64, num: u64) {
+		let mut counters = self.event_counters[event as usize] & !self.written &
+			!self.mode_inhibited_counters[self.mode];
+		while counters != 0 {
+			let index = counters.trailing_zeros() as usize;
+			let (value, overflow) = self.counters[index].overflowing_add(num);
+			self.counters[index] = value;
+			if overflow && (self.events[index] & HPM_EVENT_OF) == 0 {
+				self.events[index] |= HPM_EVENT_OF;
+				self.overflowed = true;
+			}
+			counters &= counters - 1;
+		}
+	}
+
+	/// Advances `mcycle` and, if an instruction retires in the cycle,
+	/// `minstret` unless they are inhibited or written in the cycle.
+	/// `Cpu` needs to call this method at the end of every cycle.
+	///
+	/// # Arguments
+	/// * `retired` Whether an instruction retires in the cycle
+	pub fn tick(&mut self, retired: bool) {
+		let stopped = self.inhibit | self.written;
+		if (stopped & (1 << HPM_COUNTER_CYCLE)) == 0 {
+			self.counters[HPM_COUNTER_CYCLE] = self.counters[HPM_COUNTER_CYCLE].wrapping_add(1);
+		}
+		if retired && (stopped & (1 << HPM_COUNTER_INSTRET)) == 0 {
+			self.counters[HPM_COUNTER_INSTRET] = self.counters[HPM_COUNTER_INSTRET].wrapping_add(1);
+		}
+		self.written = 0;
+	}
+}
+
+#[cfg(test)]
+mod test_hpm {
+	use super::*;
+
+	#[test]
+	fn cycle_and_instret() {
+		let mut hpm = Hpm::new();
+		hpm.tick(true);
+		hpm.tick(false);
+		assert_eq!(2, hpm.read_counter(HPM_COUNTER_CYCLE));
+		assert_eq!(1, hpm.read_counter(HPM_COUNTER_INSTRET));
+
+		// Written counter doesn't count the writing instruction
+		hpm.write_counter(HPM_COUNTER_INSTRET, 10);
+		hpm.tick(true);
+		assert_eq!(10, hpm.read_counter(HPM_COUNTER_INSTRET));
+		hpm.tick(true);
+		assert_eq!(11, hpm.read_counter(HPM_COUNTER_INSTRET));
+
+		// CY and IR in mcountinhibit. TM is hardwired to zero
+		hpm.write_inhibit(0x7);
+		assert_eq!(0x5, hpm.read_inhibit());
+		hpm.tick(true);
+		assert_eq!(4, hpm.read_counter(HPM_COUNTER_CYCLE));
+		assert_eq!(11, hpm.read_counter(HPM_COUNTER_INSTRET));
+	}
+
+	#[test]
+	fn event() {
+		let mut hpm = Hpm::new();
+		assert!(!hpm.is_counting_events());
+		hpm.write_event(3, HPM_EVENT_LOAD);
+		hpm.write_event(4, HPM_EVENT_LOAD);
+		hpm.write_event(31, HPM_EVENT_TRAP);
+		hpm.write_event(5, 0x100);
+		assert_eq!(HPM_EVENT_NONE, hpm.read_event(5));
+		assert!(hpm.is_counting_events());
+
+		hpm.count_event(HPM_EVENT_LOAD, 2);
+		hpm.count_event(HPM_EVENT_STORE, 1);
+		hpm.count_event(HPM_EVENT_TRAP, 1);
+		assert_eq!(2, hpm.read_counter(3));
+		assert_eq!(2, hpm.read_counter(4));
+		assert_eq!(0, hpm.read_counter(5));
+		assert_eq!(1, hpm.read_counter(31));
+
+		hpm.write_inhibit(1 << 4);
+		hpm.count_event(HPM_EVENT_LOAD, 1);
+		assert_eq!(3, hpm.read_counter(3));
+		assert_eq!(2, hpm.read_counter(4));
+
+		hpm.write_event(3, HPM_EVENT_NONE);
+		hpm.write_event(31, HPM_EVENT_NONE);
+		assert!(!hpm.is_counting_events());
+	}
+
+	#[test]
+	fn mode_inhibit() {
+		let mut hpm = Hpm::new();
+		hpm.write_event(3, HPM_EVENT_STORE | HPM_EVENT_MINH | HPM_EVENT_VUINH);
+		assert_eq!(HPM_EVENT_STORE | HPM_EVENT_MINH | HPM_EVENT_VUINH, hpm.read_event(3));
+		hpm.count_event(HPM_EVENT_STORE, 1);
+		hpm.update_privilege_mode(&PrivilegeMode::User, true);
+		hpm.count_event(HPM_EVENT_STORE, 1);
+		assert_eq!(0, hpm.read_counter(3));
+		hpm.update_privilege_mode(&PrivilegeMode::User, false);
+		hpm.count_event(HPM_EVENT_STORE, 1);
+		hpm.update_privilege_mode(&PrivilegeMode::Supervisor, true);
+		hpm.count_event(HPM_EVENT_STORE, 1);
+		assert_eq!(2, hpm.read_counter(3));
+	}
+
+	#[test]
+	fn overflow() {
+		let mut hpm = Hpm::new();
+		hpm.write_event(10, HPM_EVENT_BRANCH);
+		hpm.write_counter(10, 0xfffffffffffffffe);
+		hpm.tick(false);
+		hpm.count_event(HPM_EVENT_BRANCH, 1);
+		assert!(!hpm.take_overflow());
+		hpm.count_event(HPM_EVENT_BRANCH, 1);
+		assert_eq!(0, hpm.read_counter(10));
+		assert_eq!(HPM_EVENT_BRANCH | HPM_EVENT_OF, hpm.read_event(10));
+		assert_eq!(1 << 10, hpm.read_overflows());
+		assert!(hpm.take_overflow());
+		assert!(!hpm.take_overflow());
+
+		// No interrupt while overflow bit is set
+		hpm.write_counter(10, 0xffffffffffffffff);
+		hpm.tick(false);
+		hpm.count_event(HPM_EVENT_BRANCH, 1);
+		assert!(!hpm.take_overflow());
+
+		// Clearing overflow bit rearms
+		hpm.write_event(10, HPM_EVENT_BRANCH);
+		assert_eq!(0, hpm.read_overflows());
+		hpm.write_counter(10, 0xffffffffffffffff);
+		hpm.tick(false);
+		hpm.count_event(HPM_EVENT_BRANCH, 1);
+		assert!(hpm.take_overflow());
+	}
+}
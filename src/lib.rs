@@ -5,6 +5,7 @@ const PROGRAM_MEMORY_CAPACITY: u64 = 1024 * 1024 * 128; // big enough to run Lin
 extern crate fnv;
 
 use self::fnv::FnvHashMap;
+use std::path::PathBuf;
 
 pub mod cpu;
 pub mod terminal;
@@ -13,9 +14,15 @@ pub mod memory;
 pub mod mmu;
 pub mod elf_analyzer;
 pub mod device;
-
-use cpu::{Cpu, Xlen};
-use elf_analyzer::{ElfAnalyzer};
+pub mod fpu;
+pub mod hpm;
+pub mod pmp;
+pub mod semihosting;
+pub mod vpu;
+
+use cpu::{Cpu, EbreakHook, Xlen};
+use elf_analyzer::{ElfAnalyzer, Header, LoadError, ProgramHeader, SectionHeader, EF_RISCV_RVE, PT_LOAD};
+use mmu::{DRAM_BASE, MisalignedAccessPolicy};
 use terminal::Terminal;
 
 /// RISC-V emulator. It emulates RISC-V CPU and peripheral devices.
@@ -25,7 +32,7 @@ use terminal::Terminal;
 /// // Creates an emulator with arbitary terminal
 /// let mut emulator = Emulator::new(Box::new(DefaultTerminal::new()));
 /// // Set up program content binary
-/// emulator.setup_program(program_content);
+/// emulator.setup_program(program_content).unwrap();
 /// // Set up Filesystem content binary
 /// emulator.setup_filesystem(fs_content);
 /// // Go!
@@ -75,9 +82,10 @@ impl Emulator {
 		};
 	}
 
-	/// Runs program set by `setup_program()`. The emulator won't stop forever.
+	/// Runs program set by `setup_program()`. The emulator won't stop forever
+	/// unless the program exits with semihosting call.
 	pub fn run_program(&mut self) {
-		loop {
+		while self.cpu.get_exit_code().is_none() {
 			self.tick();
 		}
 	}
@@ -97,6 +105,10 @@ impl Emulator {
 
 			self.tick();
 
+			if self.cpu.get_exit_code().is_some() {
+				break;
+			}
+
 			// It seems in riscv-tests ends with end code
 			// written to a certain physical memory address
 			// (0x80001000 in mose test cases) so checking
@@ -134,23 +146,19 @@ impl Emulator {
 	}
 
 	/// Sets up program run by the program. This method analyzes the passed content
-	/// and configure CPU properly. If the passed contend doesn't seem ELF file,
-	/// it panics. This method is expected to be called only once.
+	/// and configure CPU properly. If the passed contend doesn't seem RISC-V ELF file
+	/// or it is malformed, this method returns `Err`.
+	/// This method is expected to be called only once.
 	///
 	/// # Arguments
 	/// * `data` Program binary
 	// @TODO: Make ElfAnalyzer and move the core logic there.
-	// @TODO: Returns `Err` if the passed contend doesn't seem ELF file
-	pub fn setup_program(&mut self, data: Vec<u8>) {
+	pub fn setup_program(&mut self, data: Vec<u8>) -> Result<(), LoadError> {
 		let analyzer = ElfAnalyzer::new(data);
 
-		if !analyzer.validate() {
-			panic!("This file does not seem ELF file");
-		}
-
-		let header = analyzer.read_header();
-		//let program_headers = analyzer._read_program_headers(&header);
-		let section_headers = analyzer.read_section_headers(&header);
+		let header = analyzer.read_header()?;
+		let program_headers = analyzer.read_program_headers(&header)?;
+		let section_headers = analyzer.read_section_headers(&header)?;
 
 		let mut program_data_section_headers = vec![];
 		let mut symbol_table_section_headers = vec![];
@@ -165,86 +173,129 @@ impl Emulator {
 			};
 		}
 
+		// Program is loaded by loadable segments. Sections are used only for
+		// symbols and riscv-tests detection so stripped binaries or binaries
+		// without section table are also loadable.
+		let mut load_segments = program_headers.iter()
+			.filter(|h| h.p_type == PT_LOAD && h.p_memsz > 0)
+			.collect::<Vec<&ProgramHeader>>();
+		if load_segments.is_empty() {
+			return Err(LoadError::NoLoadableSegment);
+		}
+		load_segments.sort_by_key(|h| h.p_paddr);
+
 		// Find program data section named .tohost to detect if the elf file is riscv-tests
-		self.tohost_addr = match analyzer.find_tohost_addr(
+		let tohost_addr = match analyzer.find_tohost_addr(
 			&program_data_section_headers,
 			&string_table_section_headers) {
 			Some(address) => address,
 			None => 0
 		};
 
-		// Creates symbol - virtual address mapping
-		if string_table_section_headers.len() > 0 {
-			let entries = analyzer.read_symbol_entries(&header, &symbol_table_section_headers);
-			// Assuming symbols are in the first string table section.
-			// @TODO: What if symbol can be in the second or later string table sections?
-			let map = analyzer.create_symbol_map(&entries, &string_table_section_headers[0]);
-			for key in map.keys() {
-				self.symbol_map.insert(key.to_string(), *map.get(key).unwrap());
+		// Detected whether the elf file is riscv-tests.
+		// Setting up CPU and Memory depending on it.
+
+		let memory_capacity = match tohost_addr != 0 {
+			true => TEST_MEMORY_CAPACITY,
+			false => PROGRAM_MEMORY_CAPACITY
+		};
+
+		// Validates everything before updating the emulator state
+		// so that the emulator is untouched if the program is malformed.
+
+		for i in 0..load_segments.len() {
+			let segment = load_segments[i];
+			if !is_in_ram(segment.p_paddr, segment.p_memsz, memory_capacity) {
+				return Err(LoadError::SegmentOutsideRam(segment.p_paddr));
+			}
+			// Segments are sorted by physical address so checking only the previous one
+			if i > 0 && load_segments[i - 1].p_paddr + load_segments[i - 1].p_memsz > segment.p_paddr {
+				return Err(LoadError::OverlappingSegments(segment.p_paddr));
 			}
 		}
 
-		// Detected whether the elf file is riscv-tests.
-		// Setting up CPU and Memory depending on it.
+		// Entry point is virtual address. CPU starts without address translation
+		// so converting it to physical address.
+		let entry = match load_segments.iter().find(|h| {
+			header.e_entry >= h.p_vaddr && header.e_entry - h.p_vaddr < h.p_memsz
+		}) {
+			Some(segment) => segment.p_paddr + (header.e_entry - segment.p_vaddr),
+			None => header.e_entry
+		};
+
+		if !is_in_ram(entry, 1, memory_capacity) {
+			return Err(LoadError::EntryPointOutsideRam(header.e_entry));
+		}
+
+		self.tohost_addr = tohost_addr;
+		self.load_symbols(&analyzer, &header, &symbol_table_section_headers,
+			&string_table_section_headers);
 
 		self.cpu.update_xlen(match header.e_width {
 			32 => Xlen::Bit32,
-			64 => Xlen::Bit64,
-			_ => panic!("No happen")
+			_ => Xlen::Bit64
 		});
+		self.cpu.enable_embedded_mode((header.e_flags & EF_RISCV_RVE) != 0);
 
-		if self.tohost_addr != 0 {
-			self.is_test = true;
-			self.cpu.get_mut_mmu().init_memory(TEST_MEMORY_CAPACITY);
-		} else {
-			self.is_test = false;
-			self.cpu.get_mut_mmu().init_memory(PROGRAM_MEMORY_CAPACITY);
-		}
+		self.is_test = self.tohost_addr != 0;
+		self.cpu.get_mut_mmu().init_memory(memory_capacity);
 
-		for i in 0..program_data_section_headers.len() {
-			let sh_addr = program_data_section_headers[i].sh_addr;
-			let sh_offset = program_data_section_headers[i].sh_offset as usize;
-			let sh_size = program_data_section_headers[i].sh_size as usize;
-			if sh_addr >= 0x80000000 && sh_offset > 0 && sh_size > 0 {
-				for j in 0..sh_size {
-					self.cpu.get_mut_mmu().store_raw(sh_addr + j as u64, analyzer.read_byte(sh_offset + j));
-				}
+		for segment in load_segments.iter() {
+			// The bytes not in the file, e.g. .bss, are zero-filled
+			for i in 0..segment.p_memsz {
+				let value = match i < segment.p_filesz {
+					true => analyzer.read_byte((segment.p_offset + i) as usize),
+					false => 0
+				};
+				self.cpu.get_mut_mmu().store_raw(segment.p_paddr + i, value);
 			}
 		}
 
-		self.cpu.update_pc(header.e_entry);
+		self.cpu.update_pc(entry);
+		Ok(())
 	}
 
 	/// Loads symbols of program and adds them to `symbol_map`.
+	/// If the passed content doesn't seem RISC-V ELF file or it is malformed,
+	/// this method returns `Err`.
 	///
 	/// # Arguments
 	/// * `content` Program binary
-	pub fn load_program_for_symbols(&mut self, content: Vec<u8>) {
+	pub fn load_program_for_symbols(&mut self, content: Vec<u8>) -> Result<(), LoadError> {
 		let analyzer = ElfAnalyzer::new(content);
 
-		if !analyzer.validate() {
-			panic!("This file does not seem ELF file");
-		}
-
-		let header = analyzer.read_header();
-		let section_headers = analyzer.read_section_headers(&header);
+		let header = analyzer.read_header()?;
+		let section_headers = analyzer.read_section_headers(&header)?;
 
-		let mut program_data_section_headers = vec![];
 		let mut symbol_table_section_headers = vec![];
 		let mut string_table_section_headers = vec![];
 
 		for i in 0..section_headers.len() {
 			match section_headers[i].sh_type {
-				1 => program_data_section_headers.push(&section_headers[i]),
 				2 => symbol_table_section_headers.push(&section_headers[i]),
 				3 => string_table_section_headers.push(&section_headers[i]),
 				_ => {}
 			};
 		}
 
-		// Creates symbol - virtual address mapping
-		if string_table_section_headers.len() > 0 {
-			let entries = analyzer.read_symbol_entries(&header, &symbol_table_section_headers);
+		self.load_symbols(&analyzer, &header, &symbol_table_section_headers,
+			&string_table_section_headers);
+		Ok(())
+	}
+
+	/// Helper method. Creates symbol - virtual address mapping
+	/// and adds it to `symbol_map`.
+	///
+	/// # Arguments
+	/// * `analyzer`
+	/// * `header`
+	/// * `symbol_table_section_headers`
+	/// * `string_table_section_headers`
+	fn load_symbols(&mut self, analyzer: &ElfAnalyzer, header: &Header,
+		symbol_table_section_headers: &Vec<&SectionHeader>,
+		string_table_section_headers: &Vec<&SectionHeader>) {
+		if !string_table_section_headers.is_empty() {
+			let entries = analyzer.read_symbol_entries(header, symbol_table_section_headers);
 			// Assuming symbols are in the first string table section.
 			// @TODO: What if symbol can be in the second or later string table sections?
 			let map = analyzer.create_symbol_map(&entries, &string_table_section_headers[0]);
@@ -273,6 +324,23 @@ impl Emulator {
 		self.cpu.get_mut_mmu().init_dtb(content);
 	}
 
+	/// Sets up semihosting. The program can open files under `root`
+	/// directory, use the terminal as console, and exit with an exit code
+	/// via semihosting calls.
+	/// See [`Semihosting`](./semihosting/struct.Semihosting.html) for the detail.
+	///
+	/// # Arguments
+	/// * `root` Host directory which files are opened in
+	/// * `command_line` Command line passed to the program
+	pub fn setup_semihosting(&mut self, root: PathBuf, command_line: String) {
+		self.cpu.enable_semihosting(root, command_line);
+	}
+
+	/// Returns the exit code if the program has exited with semihosting call.
+	pub fn get_exit_code(&self) -> Option<i32> {
+		self.cpu.get_exit_code()
+	}
+
 	/// Updates XLEN (the width of an integer register in bits) in CPU.
 	///
 	/// # Arguments
@@ -281,14 +349,73 @@ impl Emulator {
 		self.cpu.update_xlen(xlen);
 	}
 
-	/// Enables or disables page cache optimization.
-	/// Page cache optimization is experimental feature.
+	/// Enables or disables RV32E and RV64E, the base integer instruction set
+	/// having only 16 integer registers. [`setup_program()`](#method.setup_program)
+	/// sets it from the RVE flag of the ELF header so call this method after that
+	/// to override. See [`Cpu`](./cpu/struct.Cpu.html) for the detail.
+	///
+	/// # Arguments
+	/// * `enabled`
+	pub fn enable_embedded_mode(&mut self, enabled: bool) {
+		self.cpu.enable_embedded_mode(enabled);
+	}
+
+	/// Enables or disables strict mode. In strict mode, the emulator panics
+	/// on an undecodable instruction instead of raising IllegalInstruction trap.
+	///
+	/// # Arguments
+	/// * `enabled`
+	pub fn enable_strict_mode(&mut self, enabled: bool) {
+		self.cpu.enable_strict_mode(enabled);
+	}
+
+	/// Sets how misaligned loads and stores are handled, transparently
+	/// emulated or trapped. See [`Mmu`](./mmu/struct.Mmu.html) for the detail.
+	///
+	/// # Arguments
+	/// * `policy`
+	pub fn set_misaligned_access_policy(&mut self, policy: MisalignedAccessPolicy) {
+		self.cpu.get_mut_mmu().set_misaligned_access_policy(policy);
+	}
+
+	/// Sets vector register length in bits of the vector extension.
+	/// Default is 128. See [`Cpu`](./cpu/struct.Cpu.html) for the detail.
+	///
+	/// # Arguments
+	/// * `vlen` Must be a power of two from 64 to 65536
+	pub fn set_vlen(&mut self, vlen: usize) {
+		self.cpu.set_vlen(vlen);
+	}
+
+	/// Sets cache block size in bytes for cache-block operations. Default is 64.
+	/// The device tree is also updated so call this method after `setup_dtb()`.
 	/// See [`Mmu`](./mmu/struct.Mmu.html) for the detail.
 	///
 	/// # Arguments
+	/// * `size` Must be a power of two from 8 to 4096
+	pub fn set_cache_block_size(&mut self, size: u64) {
+		self.cpu.get_mut_mmu().set_cache_block_size(size);
+	}
+
+	/// Enables or disables decode cache in front of the table-driven decoder.
+	/// Disabled by default. See [`Cpu`](./cpu/struct.Cpu.html) for the detail.
+	///
+	/// # Arguments
 	/// * `enabled`
-	pub fn enable_page_cache(&mut self, enabled: bool) {
-		self.cpu.get_mut_mmu().enable_page_cache(enabled);
+	pub fn enable_decode_cache(&mut self, enabled: bool) {
+		self.cpu.enable_decode_cache(enabled);
+	}
+
+	/// Sets a hook intercepting `EBREAK` before the program sees it, for
+	/// example for software breakpoints of host tools. If the hook returns
+	/// `true` the program continues without Breakpoint trap.
+	/// See [`Cpu::set_ebreak_hook`](./cpu/struct.Cpu.html#method.set_ebreak_hook)
+	/// for the detail. `None` removes the hook.
+	///
+	/// # Arguments
+	/// * `hook`
+	pub fn set_ebreak_hook(&mut self, hook: Option<EbreakHook>) {
+		self.cpu.set_ebreak_hook(hook);
 	}
 
 	/// Returns mutable reference to `Terminal`.
@@ -318,6 +445,19 @@ impl Emulator {
 	}
 }
 
+/// Checks if `size` bytes from physical address `address` are in the emulated RAM
+///
+/// # Arguments
+/// * `address`
+/// * `size`
+/// * `capacity` RAM capacity
+fn is_in_ram(address: u64, size: u64, capacity: u64) -> bool {
+	match address.checked_add(size) {
+		Some(end) => address >= DRAM_BASE && end <= DRAM_BASE + capacity,
+		None => false
+	}
+}
+
 #[cfg(test)]
 mod test_emulator {
 	use terminal::DummyTerminal;
@@ -329,6 +469,53 @@ mod test_emulator {
 		)
 	}
 
+	// Loadable segment for create_elf()
+	struct Segment {
+		vaddr: u64,
+		paddr: u64,
+		content: Vec<u8>,
+		memsz: u64
+	}
+
+	// Creates a minimal 64-bit RISC-V ELF content having ELF header,
+	// program headers, and segment contents. No section table.
+	fn create_elf(entry: u64, segments: &[Segment]) -> Vec<u8> {
+		let mut data = vec![0; 0x40];
+		data[0..4].copy_from_slice(&[0x7f, 0x45, 0x4c, 0x46]);
+		data[4] = 2; // 64-bit
+		data[5] = 1; // little endian
+		data[6] = 1; // version
+		data[0x12] = 0xf3; // RISC-V
+		data[0x18..0x20].copy_from_slice(&entry.to_le_bytes());
+		data[0x20..0x28].copy_from_slice(&0x40u64.to_le_bytes()); // e_phoff
+		data[0x38..0x3a].copy_from_slice(&(segments.len() as u16).to_le_bytes()); // e_phnum
+		let mut offset = 0x40 + 0x38 * segments.len() as u64;
+		for segment in segments {
+			let mut header = vec![0; 0x38];
+			header[0..4].copy_from_slice(&PT_LOAD.to_le_bytes());
+			header[0x8..0x10].copy_from_slice(&offset.to_le_bytes());
+			header[0x10..0x18].copy_from_slice(&segment.vaddr.to_le_bytes());
+			header[0x18..0x20].copy_from_slice(&segment.paddr.to_le_bytes());
+			header[0x20..0x28].copy_from_slice(&(segment.content.len() as u64).to_le_bytes());
+			header[0x28..0x30].copy_from_slice(&segment.memsz.to_le_bytes());
+			data.extend(header);
+			offset += segment.content.len() as u64;
+		}
+		for segment in segments {
+			data.extend(&segment.content);
+		}
+		data
+	}
+
+	fn create_simple_elf(entry: u64) -> Vec<u8> {
+		create_elf(entry, &[Segment {
+			vaddr: DRAM_BASE,
+			paddr: DRAM_BASE,
+			content: vec![0x13, 0, 0, 0], // nop
+			memsz: 4
+		}])
+	}
+
 	#[test]
 	fn initialize() {
 		let _emu = create_emu();
@@ -355,13 +542,104 @@ mod test_emulator {
 	}
 
 	#[test]
-	#[ignore]
 	fn setup_program() {
+		let mut emu = create_emu();
+		// .bss like segment whose memory size is bigger than file size
+		// and the segment whose physical address is different from virtual address
+		let elf = create_elf(0xffffffff80001000, &[Segment {
+			vaddr: DRAM_BASE,
+			paddr: DRAM_BASE,
+			content: vec![1, 2, 3, 4],
+			memsz: 8
+		}, Segment {
+			vaddr: 0xffffffff80001000,
+			paddr: DRAM_BASE + 0x1000,
+			content: vec![5, 6],
+			memsz: 2
+		}]);
+		assert_eq!(Ok(()), emu.setup_program(elf));
+		// Entry point is converted to physical address
+		assert_eq!(DRAM_BASE + 0x1000, emu.get_cpu().read_pc());
+		let mmu = emu.get_mut_cpu().get_mut_mmu();
+		assert_eq!(0x04030201, mmu.load_word_raw(DRAM_BASE));
+		assert_eq!(0, mmu.load_word_raw(DRAM_BASE + 4));
+		assert_eq!(0x0605, mmu.load_word_raw(DRAM_BASE + 0x1000));
+		assert!(!emu.get_cpu().is_embedded_mode());
+
+		// RV64E program
+		let mut elf = create_simple_elf(DRAM_BASE);
+		elf[0x30..0x34].copy_from_slice(&EF_RISCV_RVE.to_le_bytes()); // e_flags
+		assert_eq!(Ok(()), emu.setup_program(elf));
+		assert!(emu.get_cpu().is_embedded_mode());
+	}
+
+	#[test]
+	fn setup_program_malformed() {
+		let mut emu = create_emu();
+		assert_eq!(Err(LoadError::BadMagic), emu.setup_program(vec![]));
+		assert_eq!(Err(LoadError::BadMagic), emu.setup_program(vec![0; 0x40]));
+
+		let elf = create_simple_elf(DRAM_BASE);
+		assert_eq!(Err(LoadError::TruncatedHeader), emu.setup_program(elf[0..8].to_vec()));
+		assert_eq!(Err(LoadError::TruncatedHeader), emu.setup_program(elf[0..0x30].to_vec()));
+		// Program header table past the end of the file
+		assert_eq!(Err(LoadError::TruncatedHeader), emu.setup_program(elf[0..0x50].to_vec()));
+		// Segment content past the end of the file
+		assert_eq!(Err(LoadError::SegmentOutOfBounds(0)), emu.setup_program(elf[0..0x7a].to_vec()));
+
+		let mut elf = create_simple_elf(DRAM_BASE);
+		elf[4] = 3;
+		assert_eq!(Err(LoadError::UnsupportedClass(3)), emu.setup_program(elf));
+
+		let mut elf = create_simple_elf(DRAM_BASE);
+		elf[0x12] = 0x3e; // x86-64
+		assert_eq!(Err(LoadError::UnsupportedMachine(0x3e)), emu.setup_program(elf));
+
+		// Section header table past the end of the file
+		let mut elf = create_simple_elf(DRAM_BASE);
+		let shoff = elf.len() as u64;
+		elf[0x28..0x30].copy_from_slice(&shoff.to_le_bytes());
+		elf[0x3c] = 1; // e_shnum
+		assert_eq!(Err(LoadError::TruncatedHeader), emu.setup_program(elf.clone()));
+
+		// Section content past the end of the file
+		let mut section_header = vec![0; 0x40];
+		section_header[4] = 1; // sh_type
+		section_header[0x18] = 0x80; // sh_offset
+		section_header[0x20] = 0x80; // sh_size
+		elf.extend(section_header);
+		assert_eq!(Err(LoadError::SectionOutOfBounds(0)), emu.setup_program(elf));
+
+		assert_eq!(Err(LoadError::NoLoadableSegment), emu.setup_program(create_elf(DRAM_BASE, &[])));
+
+		assert_eq!(Err(LoadError::SegmentOutsideRam(0x1000)), emu.setup_program(create_elf(0x1000, &[Segment {
+			vaddr: 0x1000,
+			paddr: 0x1000,
+			content: vec![0x13, 0, 0, 0],
+			memsz: 4
+		}])));
+
+		assert_eq!(Err(LoadError::OverlappingSegments(DRAM_BASE + 4)), emu.setup_program(create_elf(DRAM_BASE, &[Segment {
+			vaddr: DRAM_BASE + 4,
+			paddr: DRAM_BASE + 4,
+			content: vec![],
+			memsz: 4
+		}, Segment {
+			vaddr: DRAM_BASE,
+			paddr: DRAM_BASE,
+			content: vec![0x13, 0, 0, 0],
+			memsz: 8
+		}])));
+
+		assert_eq!(Err(LoadError::EntryPointOutsideRam(0x1000)),
+			emu.setup_program(create_simple_elf(0x1000)));
 	}
 
 	#[test]
-	#[ignore]
 	fn load_program_for_symbols() {
+		let mut emu = create_emu();
+		assert_eq!(Ok(()), emu.load_program_for_symbols(create_simple_elf(DRAM_BASE)));
+		assert_eq!(Err(LoadError::BadMagic), emu.load_program_for_symbols(vec![0; 4]));
 	}
 
 	#[test]
@@ -379,11 +657,6 @@ mod test_emulator {
 	fn update_xlen() {
 	}
 
-	#[test]
-	#[ignore]
-	fn enable_page_cache() {
-	}
-
 	#[test]
 	#[ignore]
 	fn get_addredd_of_symbol() {
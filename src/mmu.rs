@@ -9,18 +9,18 @@ extern crate fnv;
 use self::fnv::FnvHashMap;
 
 use memory::Memory;
-use cpu::{PrivilegeMode, Trap, TrapType, Xlen, get_privilege_mode};
+use cpu::{PrivilegeMode, Trap, TrapType, Xlen, get_privilege_mode, MSTATUS_MPRV, MSTATUS_MPV, MSTATUS_MXR, MSTATUS_SUM};
 use device::virtio_block_disk::VirtioBlockDisk;
 use device::plic::Plic;
 use device::clint::Clint;
 use device::uart::Uart;
+use pmp::{Pmp, PMP_R, PMP_W, PMP_X};
 use terminal::Terminal;
 
 /// Emulates Memory Management Unit. It holds the Main memory and peripheral
 /// devices, maps address to them, and accesses them depending on address.
 /// It also manages virtual-physical address translation and memoty protection.
 /// It may also be said Bus.
-/// @TODO: Memory protection is not implemented yet. We should support.
 pub struct Mmu {
 	clock: u64,
 	xlen: Xlen,
@@ -33,48 +33,244 @@ pub struct Mmu {
 	plic: Plic,
 	clint: Clint,
 	uart: Uart,
+	pmp: Pmp,
 
 	/// Address translation can be affected `mstatus` (MPRV, MPP in machine mode)
 	/// then `Mmu` has copy of it.
 	mstatus: u64,
 
-	/// Address translation page cache. Experimental feature.
-	/// The cache is cleared when translation mapping can be changed;
-	/// xlen, ppn, privilege_mode, or addressing_mode is updated.
-	/// Precisely it isn't good enough because page table entries
-	/// can be updated anytime with store instructions, of course
-	/// very depending on how pages are mapped tho.
-	/// But observing all page table entries is high cost so
-	/// ignoring so far. Then this cache optimization can cause a bug
-	/// due to unexpected (meaning not in page fault handler)
-	/// page table entry update. So this is experimental feature and
-	/// disabled by default. If you want to enable, use `enable_page_cache()`.
-	page_cache_enabled: bool,
-	fetch_page_cache: FnvHashMap<u64, u64>,
-	load_page_cache: FnvHashMap<u64, u64>,
-	store_page_cache: FnvHashMap<u64, u64>
+	/// Address space identifier in `satp`
+	asid: u64,
+
+	/// Virtualization mode. VS-mode and VU-mode are supervisor and user mode
+	/// in virtualization mode, whose addresses are translated in two stages.
+	/// VS-stage translates guest virtual address into guest physical address
+	/// with `vsatp`, and G-stage translates it into physical address with `hgatp`.
+	virtualization_mode: bool,
+
+	/// Privilege mode of hypervisor virtual-machine load or store, HLV, HLVX,
+	/// or HSV, in progress. The access is translated as the one in this mode
+	/// in virtualization mode.
+	hypervisor_access: Option<PrivilegeMode>,
+
+	/// VS-stage translation copies of `vsatp`
+	vs_addressing_mode: AddressingMode,
+	vs_ppn: u64,
+	vs_asid: u64,
+
+	/// G-stage translation copies of `hgatp`. Sv32x4, Sv39x4, Sv48x4, and Sv57x4
+	/// are represented as the addressing modes they extend.
+	g_addressing_mode: AddressingMode,
+	g_ppn: u64,
+	vmid: u64,
+
+	/// VS-stage translation can be affected `vsstatus` (SUM, MXR) then `Mmu`
+	/// has copy of it.
+	vsstatus: u64,
+
+	/// Guest physical address of the latest guest-page fault. `Cpu` reads it
+	/// on the trap for `htval` or `mtval2`.
+	guest_fault_address: u64,
+
+	/// Translation lookaside buffer. Caches leaf page table entries keyed by
+	/// translation context and virtual page number. The context packs VMID for
+	/// guest translation and ASID into a word, see `get_tlb_context()`. Permissions
+	/// are checked against the cached entry on every access, so privilege mode
+	/// and `mstatus` updates don't need flush. As the specification requires,
+	/// software must execute SFENCE.VMA, HFENCE.VVMA, or HFENCE.GVMA after
+	/// updating page tables to flush stale entries.
+	tlb: FnvHashMap<(u64, u64), TlbEntry>,
+
+	/// The numbers of instruction fetch and data address translations missing
+	/// TLB since `Cpu` took them last time. `Cpu` counts them as performance
+	/// monitor events.
+	itlb_misses: u64,
+	dtlb_misses: u64,
+
+	/// LR/SC reservation set. Holds the physical address of the reserved
+	/// granule. Store to the granule or trap invalidates the reservation.
+	reservation: Option<u64>,
+
+	misaligned_access_policy: MisalignedAccessPolicy,
+
+	/// Cache block size in bytes for cache-block operations. No cache is
+	/// emulated but CBO.ZERO zeroes the whole block.
+	cache_block_size: u64
+}
+
+/// Size of LR/SC reservation set in bytes. Must be power of two.
+const RESERVATION_GRANULE: u64 = 64;
+
+/// Default cache block size in bytes, advertised in the default device tree.
+const DEFAULT_CACHE_BLOCK_SIZE: u64 = 64;
+
+/// The maximum number of TLB entries. TLB is flushed when it's full.
+const TLB_ENTRY_NUM: usize = 4096;
+
+const PTE_R: u64 = 0x02;
+const PTE_W: u64 = 0x04;
+const PTE_X: u64 = 0x08;
+const PTE_U: u64 = 0x10;
+const PTE_G: u64 = 0x20;
+const PTE_A: u64 = 0x40;
+const PTE_D: u64 = 0x80;
+
+/// TLB entry. Superpage is cached per 4KiB page.
+struct TlbEntry {
+	/// Physical address of the 4KiB page
+	p_page: u64,
+
+	/// Leaf page table entry. VS-stage one for guest translation.
+	pte: u64,
+
+	/// Leaf page table entry of G-stage for guest translation
+	g_pte: u64,
+
+	/// Virtual page number bits mapped by superpage as they are.
+	/// Used to flush all the 4KiB pages of a superpage.
+	superpage_mask: u64
+}
+
+/// How misaligned loads and stores are handled.
+pub enum MisalignedAccessPolicy {
+	/// Handles misaligned accesses transparently. An access crossing
+	/// a page boundary translates the both pages.
+	Emulate,
+
+	/// Raises address misaligned exception so that software, for example
+	/// firmware, can emulate the access.
+	Trap
 }
 
 pub enum AddressingMode {
 	None,
 	SV32,
 	SV39,
-	SV48 // @TODO: Implement
+	SV48,
+	SV57
 }
 
 enum MemoryAccessType {
 	Execute,
 	Read,
+	/// Read by HLVX which requires execute permission instead of read permission
+	ExecutableRead,
 	Write,
 	DontCare
 }
 
+/// Address translation stage
+enum TranslationStage {
+	/// Single-stage translation with `satp` out of virtualization mode
+	Single,
+	/// VS-stage translation with `vsatp` from guest virtual address to
+	/// guest physical address
+	VirtualSupervisor,
+	/// G-stage translation with `hgatp` from guest physical address to
+	/// physical address
+	Guest
+}
+
 fn _get_addressing_mode_name(mode: &AddressingMode) -> &'static str {
 	match mode {
 		AddressingMode::None => "None",
 		AddressingMode::SV32 => "SV32",
 		AddressingMode::SV39 => "SV39",
-		AddressingMode::SV48 => "SV48"
+		AddressingMode::SV48 => "SV48",
+		AddressingMode::SV57 => "SV57"
+	}
+}
+
+/// Creates page fault trap for the access type.
+///
+/// # Arguments
+/// * `access_type`
+/// * `v_address` Virtual address stored to `tval`
+fn create_page_fault(access_type: &MemoryAccessType, v_address: u64) -> Trap {
+	Trap {
+		trap_type: match access_type {
+			MemoryAccessType::Execute => TrapType::InstructionPageFault,
+			MemoryAccessType::Write => TrapType::StorePageFault,
+			_ => TrapType::LoadPageFault
+		},
+		value: v_address
+	}
+}
+
+/// Returns TLB translation context packing VMID, `None` for single-stage
+/// translation, above 16-bit ASID. Keeping TLB key in two words makes
+/// the lookup on every memory access cheaper.
+///
+/// # Arguments
+/// * `vmid`
+/// * `asid`
+fn get_tlb_context(vmid: Option<u64>, asid: u64) -> u64 {
+	match vmid {
+		Some(vmid) => ((vmid + 1) << 16) | asid,
+		None => asid
+	}
+}
+
+/// Returns VMID packed in TLB translation context.
+///
+/// # Arguments
+/// * `context`
+fn get_tlb_context_vmid(context: u64) -> Option<u64> {
+	match context >> 16 {
+		0 => None,
+		vmid => Some(vmid - 1)
+	}
+}
+
+/// Creates guest-page fault trap for the access type.
+///
+/// # Arguments
+/// * `access_type`
+/// * `v_address` Guest virtual address stored to `tval`
+fn create_guest_page_fault(access_type: &MemoryAccessType, v_address: u64) -> Trap {
+	Trap {
+		trap_type: match access_type {
+			MemoryAccessType::Execute => TrapType::InstructionGuestPageFault,
+			MemoryAccessType::Write => TrapType::StoreGuestPageFault,
+			_ => TrapType::LoadGuestPageFault
+		},
+		value: v_address
+	}
+}
+
+/// Creates access fault trap for the access type.
+///
+/// # Arguments
+/// * `access_type`
+/// * `v_address` Virtual address stored to `tval`
+fn create_access_fault(access_type: &MemoryAccessType, v_address: u64) -> Trap {
+	Trap {
+		trap_type: match access_type {
+			MemoryAccessType::Execute => TrapType::InstructionAccessFault,
+			MemoryAccessType::Write => TrapType::StoreAccessFault,
+			_ => TrapType::LoadAccessFault
+		},
+		value: v_address
+	}
+}
+
+/// Returns `Pmp` permission bit required by the access type.
+fn get_pmp_access(access_type: &MemoryAccessType) -> u8 {
+	match access_type {
+		MemoryAccessType::Execute => PMP_X,
+		MemoryAccessType::Write => PMP_W,
+		_ => PMP_R
+	}
+}
+
+/// Returns the number of page table levels of addressing mode.
+fn get_page_table_levels(mode: &AddressingMode) -> u8 {
+	match mode {
+		AddressingMode::None => 0,
+		AddressingMode::SV32 => 2,
+		AddressingMode::SV39 => 3,
+		AddressingMode::SV48 => 4,
+		AddressingMode::SV57 => 5
 	}
 }
 
@@ -105,11 +301,25 @@ impl Mmu {
 			plic: Plic::new(),
 			clint: Clint::new(),
 			uart: Uart::new(terminal),
+			pmp: Pmp::new(),
 			mstatus: 0,
-			page_cache_enabled: false,
-			fetch_page_cache: FnvHashMap::default(),
-			load_page_cache: FnvHashMap::default(),
-			store_page_cache: FnvHashMap::default()
+			asid: 0,
+			virtualization_mode: false,
+			hypervisor_access: None,
+			vs_addressing_mode: AddressingMode::None,
+			vs_ppn: 0,
+			vs_asid: 0,
+			g_addressing_mode: AddressingMode::None,
+			g_ppn: 0,
+			vmid: 0,
+			vsstatus: 0,
+			guest_fault_address: 0,
+			tlb: FnvHashMap::default(),
+			itlb_misses: 0,
+			dtlb_misses: 0,
+			reservation: None,
+			misaligned_access_policy: MisalignedAccessPolicy::Emulate,
+			cache_block_size: DEFAULT_CACHE_BLOCK_SIZE
 		}
 	}
 
@@ -119,7 +329,7 @@ impl Mmu {
 	/// * `xlen`
 	pub fn update_xlen(&mut self, xlen: Xlen) {
 		self.xlen = xlen;
-		self.clear_page_cache();
+		self.tlb.clear();
 	}
 
 	/// Initializes Main memory. This method is expected to be called only once.
@@ -151,20 +361,170 @@ impl Mmu {
 		}
 	}
 
-	/// Enables or disables page cache optimization.
+	/// Sets cache block size for cache-block operations. Default is 64.
+	/// `riscv,cbom-block-size`, `riscv,cbop-block-size`, and
+	/// `riscv,cboz-block-size` properties in the device tree are updated
+	/// if they exist, so call this method after `init_dtb()`.
 	///
 	/// # Arguments
-	/// * `enabled`
-	pub fn enable_page_cache(&mut self, enabled: bool) {
-		self.page_cache_enabled = enabled;
-		self.clear_page_cache();
+	/// * `size` Must be a power of two from 8 to 4096
+	pub fn set_cache_block_size(&mut self, size: u64) {
+		debug_assert!(size.is_power_of_two() && (8..=0x1000).contains(&size),
+			"Cache block size must be a power of two from 8 to 4096. {:X}", size);
+		self.cache_block_size = size;
+		for name in ["riscv,cbom-block-size", "riscv,cbop-block-size", "riscv,cboz-block-size"].iter() {
+			match self.find_dtb_property(name) {
+				Some(offset) => {
+					for i in 0..4 {
+						self.dtb[offset + i] = (size >> (24 - i * 8)) as u8;
+					}
+				},
+				None => {}
+			};
+		}
+	}
+
+	/// Returns cache block size in bytes for cache-block operations.
+	pub fn get_cache_block_size(&self) -> u64 {
+		self.cache_block_size
+	}
+
+	/// Returns the offset in device tree binary of the 32-bit value of
+	/// the property if it exists.
+	///
+	/// # Arguments
+	/// * `name` Property name
+	fn find_dtb_property(&self, name: &str) -> Option<usize> {
+		let read_u32 = |offset: usize| -> usize {
+			((self.dtb[offset] as usize) << 24) | ((self.dtb[offset + 1] as usize) << 16) |
+				((self.dtb[offset + 2] as usize) << 8) | (self.dtb[offset + 3] as usize)
+		};
+		let struct_offset = read_u32(8);
+		let strings_offset = read_u32(12);
+		let struct_end = struct_offset + read_u32(36);
+		if read_u32(0) != 0xd00dfeed || struct_end > self.dtb.len() || strings_offset > self.dtb.len() {
+			return None;
+		}
+		let name = name.as_bytes();
+		let mut offset = struct_offset;
+		while offset + 12 <= struct_end {
+			match read_u32(offset) {
+				// FDT_BEGIN_NODE followed by null-terminated node name
+				1 => {
+					let mut end = offset + 4;
+					while end < struct_end && self.dtb[end] != 0 {
+						end += 1;
+					}
+					offset = (end + 4) & !3;
+				},
+				// FDT_PROP followed by value length, name offset, and value
+				3 => {
+					let length = read_u32(offset + 4);
+					let name_offset = strings_offset + read_u32(offset + 8);
+					let name_end = name_offset + name.len();
+					if length == 4 && name_end < self.dtb.len() &&
+						&self.dtb[name_offset..name_end] == name && self.dtb[name_end] == 0 {
+						return Some(offset + 12);
+					}
+					offset += 12 + ((length + 3) & !3);
+				},
+				// FDT_END
+				9 => break,
+				// FDT_END_NODE or FDT_NOP
+				_ => offset += 4
+			};
+		}
+		None
+	}
+
+	/// Sets how misaligned loads and stores are handled. Default is
+	/// `MisalignedAccessPolicy::Emulate`.
+	///
+	/// # Arguments
+	/// * `policy`
+	pub fn set_misaligned_access_policy(&mut self, policy: MisalignedAccessPolicy) {
+		self.misaligned_access_policy = policy;
+	}
+
+	/// Flushes TLB entries, for SFENCE.VMA. Entries are flushed only for
+	/// the virtual address if `v_address` is specified, and only for
+	/// the address space if `asid` is specified. Global mappings aren't
+	/// flushed by ASID. In virtualization mode, only the guest translation
+	/// entries of the current VMID are flushed.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	/// * `asid` Address space identifier
+	pub fn flush_tlb(&mut self, v_address: Option<u64>, asid: Option<u64>) {
+		let vmid = match self.virtualization_mode {
+			true => Some(self.vmid),
+			false => None
+		};
+		self.flush_tlb_entries(vmid, v_address, asid);
+	}
+
+	/// Flushes guest translation TLB entries of the current VMID, for
+	/// HFENCE.VVMA. Arguments are the same as `flush_tlb()` in guest
+	/// address space.
+	///
+	/// # Arguments
+	/// * `v_address` Guest virtual address
+	/// * `asid` Guest address space identifier
+	pub fn flush_guest_tlb(&mut self, v_address: Option<u64>, asid: Option<u64>) {
+		let vmid = self.vmid;
+		self.flush_tlb_entries(Some(vmid), v_address, asid);
+	}
+
+	/// Flushes guest translation TLB entries, for HFENCE.GVMA. Entries are
+	/// flushed only for the virtual machine if `vmid` is specified. TLB is
+	/// indexed by guest virtual address, so all the entries of the virtual
+	/// machine are flushed even if guest physical address is specified.
+	///
+	/// # Arguments
+	/// * `vmid` Virtual machine identifier
+	pub fn flush_guest_physical_tlb(&mut self, vmid: Option<u64>) {
+		let vmid = vmid.map(|vmid| vmid & self.get_vmid_mask());
+		self.tlb.retain(|&(entry_context, _entry_vpn), _entry| {
+			match (get_tlb_context_vmid(entry_context), vmid) {
+				(None, _) => true,
+				(Some(entry_vmid), Some(vmid)) => entry_vmid != vmid,
+				(Some(_entry_vmid), None) => false
+			}
+		});
+	}
+
+	/// Flushes TLB entries of the translation context, `None` for
+	/// single-stage translation or VMID for guest translation.
+	fn flush_tlb_entries(&mut self, vmid: Option<u64>, v_address: Option<u64>, asid: Option<u64>) {
+		let vpn = v_address.map(|address| self.get_effective_address(address) >> 12);
+		let asid = asid.map(|asid| asid & self.get_asid_mask());
+		self.tlb.retain(|&(entry_context, entry_vpn), entry| {
+			let entry_vmid = get_tlb_context_vmid(entry_context);
+			let entry_asid = entry_context & 0xffff;
+			let is_address_matched = match vpn {
+				Some(vpn) => (entry_vpn & !entry.superpage_mask) == (vpn & !entry.superpage_mask),
+				None => true
+			};
+			let is_asid_matched = match asid {
+				Some(asid) => entry_asid == asid && (entry.pte & PTE_G) == 0,
+				None => true
+			};
+			!(entry_vmid == vmid && is_address_matched && is_asid_matched)
+		});
 	}
 
-	/// Clears page cache entries
-	fn clear_page_cache(&mut self) {
-		self.fetch_page_cache.clear();
-		self.load_page_cache.clear();
-		self.store_page_cache.clear();
+	fn get_asid_mask(&self) -> u64 {
+		match self.xlen {
+			Xlen::Bit32 => 0x1ff,
+			Xlen::Bit64 => 0xffff
+		}
+	}
+
+	fn get_vmid_mask(&self) -> u64 {
+		match self.xlen {
+			Xlen::Bit32 => 0x7f,
+			Xlen::Bit64 => 0x3fff
+		}
 	}
 
 	/// Runs one cycle of MMU and peripheral devices.
@@ -172,7 +532,7 @@ impl Mmu {
 		self.clint.tick(mip);
 		self.disk.tick(&mut self.memory);
 		self.uart.tick();
-		self.plic.tick(self.disk.is_interrupting(), self.uart.is_interrupting(), mip);
+		self.plic.tick(self.disk.is_interrupting(), self.uart.is_interrupting());
 		self.clock = self.clock.wrapping_add(1);
 	}
 
@@ -182,7 +542,7 @@ impl Mmu {
 	/// * `new_addressing_mode`
 	pub fn update_addressing_mode(&mut self, new_addressing_mode: AddressingMode) {
 		self.addressing_mode = new_addressing_mode;
-		self.clear_page_cache();
+		self.tlb.clear();
 	}
 
 	/// Updates privilege mode
@@ -191,7 +551,6 @@ impl Mmu {
 	/// * `mode`
 	pub fn update_privilege_mode(&mut self, mode: PrivilegeMode) {
 		self.privilege_mode = mode;
-		self.clear_page_cache();
 	}
 
 	/// Updates mstatus copy. `CPU` needs to call this method whenever
@@ -209,7 +568,93 @@ impl Mmu {
 	/// * `ppn`
 	pub fn update_ppn(&mut self, ppn: u64) {
 		self.ppn = ppn;
-		self.clear_page_cache();
+	}
+
+	/// Updates ASID used for address translation. Changing ASID doesn't
+	/// flush TLB.
+	///
+	/// # Arguments
+	/// * `asid`
+	pub fn update_asid(&mut self, asid: u64) {
+		self.asid = asid & self.get_asid_mask();
+	}
+
+	/// Updates virtualization mode
+	///
+	/// # Arguments
+	/// * `enabled`
+	pub fn update_virtualization_mode(&mut self, enabled: bool) {
+		self.virtualization_mode = enabled;
+	}
+
+	/// Starts or ends hypervisor virtual-machine load or store. While `mode`
+	/// is specified, loads and stores are translated as the ones in the mode
+	/// in virtualization mode.
+	///
+	/// # Arguments
+	/// * `mode` VS-mode (supervisor) or VU-mode (user) by `hstatus.SPVP`
+	pub fn update_hypervisor_access(&mut self, mode: Option<PrivilegeMode>) {
+		self.hypervisor_access = mode;
+	}
+
+	/// Updates VS-stage translation used in virtualization mode, from `vsatp`,
+	/// and flushes TLB.
+	///
+	/// # Arguments
+	/// * `addressing_mode`
+	/// * `ppn`
+	/// * `asid`
+	pub fn update_vs_translation(&mut self, addressing_mode: AddressingMode, ppn: u64, asid: u64) {
+		self.vs_addressing_mode = addressing_mode;
+		self.vs_ppn = ppn;
+		self.vs_asid = asid & self.get_asid_mask();
+		self.tlb.clear();
+	}
+
+	/// Updates G-stage translation used in virtualization mode, from `hgatp`,
+	/// and flushes TLB.
+	///
+	/// # Arguments
+	/// * `addressing_mode` Addressing mode extended by G-stage, for example `SV39` for Sv39x4
+	/// * `ppn` Must be aligned to 16KiB root page table
+	/// * `vmid`
+	pub fn update_guest_translation(&mut self, addressing_mode: AddressingMode, ppn: u64, vmid: u64) {
+		self.g_addressing_mode = addressing_mode;
+		self.g_ppn = ppn;
+		self.vmid = vmid & self.get_vmid_mask();
+		self.tlb.clear();
+	}
+
+	/// Updates vsstatus copy. `CPU` needs to call this method whenever
+	/// `vsstatus` is updated.
+	///
+	/// # Arguments
+	/// * `vsstatus`
+	pub fn update_vsstatus(&mut self, vsstatus: u64) {
+		self.vsstatus = vsstatus;
+	}
+
+	/// Returns the guest physical address of the latest guest-page fault.
+	pub fn get_guest_fault_address(&self) -> u64 {
+		self.guest_fault_address
+	}
+
+	/// Returns the numbers of instruction fetch and data address translations
+	/// missing TLB since the last call, and resets them.
+	pub fn take_tlb_misses(&mut self) -> (u64, u64) {
+		let misses = (self.itlb_misses, self.dtlb_misses);
+		self.itlb_misses = 0;
+		self.dtlb_misses = 0;
+		misses
+	}
+
+	fn count_tlb_miss(&mut self, access_type: &MemoryAccessType) {
+		match access_type {
+			MemoryAccessType::Execute => self.itlb_misses += 1,
+			// Not by the program
+			MemoryAccessType::DontCare => {},
+			_ => self.dtlb_misses += 1
+		};
 	}
 
 	fn get_effective_address(&self, address: u64) -> u64 {
@@ -219,54 +664,65 @@ impl Mmu {
 		}
 	}
 
-	/// Fetches an instruction byte. This method takes virtual address
+	/// Fetches an instruction. Reads the lower 16 bits first, and the upper
+	/// 16 bits only if the instruction isn't compressed, so that a compressed
+	/// instruction at the end of a page doesn't touch the next page. The upper
+	/// 16 bits of a compressed instruction are zero. A fault on the upper
+	/// half has its address as `tval`. This method takes virtual address
 	/// and translates into physical address inside.
 	///
 	/// # Arguments
 	/// * `v_address` Virtual address
-	fn fetch(&mut self, v_address: u64) -> Result<u8, Trap> {
-		match self.translate_address(v_address, &MemoryAccessType::Execute) {
-			Ok(p_address) => Ok(self.load_raw(p_address)),
-			Err(()) => return Err(Trap {
-				trap_type: TrapType::InstructionPageFault,
-				value: v_address
-			})
+	pub fn fetch_word(&mut self, v_address: u64) -> Result<u32, Trap> {
+		let effective_address = self.get_effective_address(v_address);
+		let lower_p_address = match self.translate_and_check_address(effective_address, 2, &MemoryAccessType::Execute) {
+			Ok(p_address) => p_address,
+			Err(e) => return Err(e)
+		};
+		let lower = self.load_halfword_raw(lower_p_address) as u32;
+		if (lower & 0x3) != 0x3 {
+			return Ok(lower);
 		}
+		let upper_address = self.get_effective_address(effective_address.wrapping_add(2));
+		let upper_p_address = match upper_address & 0xfff {
+			// The upper half is in the next page
+			0 => match self.translate_and_check_address(upper_address, 2, &MemoryAccessType::Execute) {
+				Ok(p_address) => p_address,
+				Err(e) => return Err(e)
+			},
+			_ => {
+				let p_address = lower_p_address.wrapping_add(2);
+				match self.check_pmp(p_address, 2, &MemoryAccessType::Execute, upper_address) {
+					Ok(()) => p_address,
+					Err(e) => return Err(e)
+				}
+			}
+		};
+		let upper = self.load_halfword_raw(upper_p_address) as u32;
+		Ok((upper << 16) | lower)
 	}
 
-	/// Fetches instruction four bytes. This method takes virtual address
-	/// and translates into physical address inside.
+	/// Fetches a word like `fetch_word()` but returns `None` if the word
+	/// isn't fetchable or not in main memory, for peeking instructions next
+	/// to the executed one without any fault or device access. This method
+	/// takes virtual address and translates into physical address inside.
 	///
 	/// # Arguments
 	/// * `v_address` Virtual address
-	pub fn fetch_word(&mut self, v_address: u64) -> Result<u32, Trap> {
-		let width = 4;
-		match (v_address & 0xfff) <= (0x1000 - width) {
-			true => {
-				// Fast path. All bytes fetched are in the same page so
-				// translating an address only once.
-				let effective_address = self.get_effective_address(v_address);
-				match self.translate_address(effective_address, &MemoryAccessType::Execute) {
-					Ok(p_address) => Ok(self.load_word_raw(p_address)),
-					Err(()) => Err(Trap {
-						trap_type: TrapType::InstructionPageFault,
-						value: effective_address
-					})
-				}
-			},
-			false => {
-				let mut data = 0 as u32;
-				for i in 0..width {
-					match self.fetch(v_address.wrapping_add(i)) {
-						Ok(byte) => {
-							data |= (byte as u32) << (i * 8)
-						},
-						Err(e) => return Err(e)
-					};
-				}
-				Ok(data)
-			}
+	pub fn probe_word(&mut self, v_address: u64) -> Option<u32> {
+		let mut word = 0;
+		for i in 0..2 {
+			let effective_address = self.get_effective_address(v_address.wrapping_add(i * 2));
+			let p_address = match self.translate_and_check_address(effective_address, 2, &MemoryAccessType::Execute) {
+				Ok(p_address) => self.get_effective_address(p_address),
+				Err(_e) => return None
+			};
+			match p_address >= DRAM_BASE && self.memory.validate_address(p_address.wrapping_add(1)) {
+				true => word |= (self.memory.read_halfword(p_address) as u32) << (i * 16),
+				false => return None
+			};
 		}
+		Some(word)
 	}
 
 	/// Loads an byte. This method takes virtual address and translates
@@ -276,26 +732,35 @@ impl Mmu {
 	/// * `v_address` Virtual address
 	pub fn load(&mut self, v_address: u64) -> Result<u8, Trap> {
 		let effective_address = self.get_effective_address(v_address);
-		match self.translate_address(effective_address, &MemoryAccessType::Read) {
+		match self.translate_and_check_address(effective_address, 1, &MemoryAccessType::Read) {
 			Ok(p_address) => Ok(self.load_raw(p_address)),
-			Err(()) => Err(Trap {
-				trap_type: TrapType::LoadPageFault,
-				value: v_address
-			})
+			Err(e) => Err(e)
 		}
 	}
 
 	/// Loads multiple bytes. This method takes virtual address and translates
-	/// into physical address inside.
+	/// into physical address inside. Misaligned access is handled depending
+	/// on `MisalignedAccessPolicy`.
 	///
 	/// # Arguments
 	/// * `v_address` Virtual address
 	/// * `width` Must be 1, 2, 4, or 8
-	fn load_bytes(&mut self, v_address: u64, width: u64) -> Result<u64, Trap> {
+	/// * `access_type` `Read`, or `ExecutableRead` for HLVX
+	fn load_bytes(&mut self, v_address: u64, width: u64, access_type: &MemoryAccessType) -> Result<u64, Trap> {
 		debug_assert!(width == 1 || width == 2 || width == 4 || width == 8,
 			"Width must be 1, 2, 4, or 8. {:X}", width);
-		match (v_address & 0xfff) <= (0x1000 - width) {
-			true => match self.translate_address(v_address, &MemoryAccessType::Read) {
+		let effective_address = self.get_effective_address(v_address);
+		match self.misaligned_access_policy {
+			MisalignedAccessPolicy::Trap => if (effective_address & (width - 1)) != 0 {
+				return Err(Trap {
+					trap_type: TrapType::LoadAddressMisaligned,
+					value: effective_address
+				});
+			},
+			MisalignedAccessPolicy::Emulate => {}
+		};
+		match (effective_address & 0xfff) <= (0x1000 - width) {
+			true => match self.translate_and_check_address(effective_address, width, access_type) {
 				Ok(p_address) => {
 					// Fast path. All bytes fetched are in the same page so
 					// translating an address only once.
@@ -307,33 +772,58 @@ impl Mmu {
 						_ => panic!("Width must be 1, 2, 4, or 8. {:X}", width)
 					}
 				},
-				Err(()) => Err(Trap {
-					trap_type: TrapType::LoadPageFault,
-					value: v_address
-				})
+				Err(e) => Err(e)
 			},
 			false => {
-				let mut data = 0 as u64;
-				for i in 0..width {
-					match self.load(v_address.wrapping_add(i)) {
-						Ok(byte) => {
-							data |= (byte as u64) << (i * 8)
-						},
-						Err(e) => return Err(e)
-					};
+				let p_addresses = match self.translate_page_crossing_address(effective_address, width, access_type) {
+					Ok(p_addresses) => p_addresses,
+					Err(e) => return Err(e)
+				};
+				let mut data = 0;
+				for (i, p_address) in p_addresses.iter().take(width as usize).enumerate() {
+					data |= (self.load_raw(*p_address) as u64) << (i * 8);
 				}
 				Ok(data)
 			}
 		}
 	}
 
+	/// Translates the both pages of the access crossing a page boundary,
+	/// and returns the physical addresses of each byte. The fault on
+	/// the second page has the address of the page as `tval`.
+	///
+	/// # Arguments
+	/// * `v_address` Effective virtual address
+	/// * `width` The number of bytes accessed, up to 8
+	/// * `access_type`
+	fn translate_page_crossing_address(&mut self, v_address: u64, width: u64, access_type: &MemoryAccessType) -> Result<[u64; 8], Trap> {
+		let first_width = 0x1000 - (v_address & 0xfff);
+		let second_v_address = self.get_effective_address(v_address.wrapping_add(first_width));
+		let first_p_address = match self.translate_and_check_address(v_address, first_width, access_type) {
+			Ok(p_address) => p_address,
+			Err(e) => return Err(e)
+		};
+		let second_p_address = match self.translate_and_check_address(second_v_address, width - first_width, access_type) {
+			Ok(p_address) => p_address,
+			Err(e) => return Err(e)
+		};
+		let mut p_addresses = [0; 8];
+		for (i, p_address) in p_addresses.iter_mut().take(width as usize).enumerate() {
+			*p_address = match (i as u64) < first_width {
+				true => first_p_address.wrapping_add(i as u64),
+				false => second_p_address.wrapping_add(i as u64 - first_width)
+			};
+		}
+		Ok(p_addresses)
+	}
+
 	/// Loads two bytes. This method takes virtual address and translates
 	/// into physical address inside.
 	///
 	/// # Arguments
 	/// * `v_address` Virtual address
 	pub fn load_halfword(&mut self, v_address: u64) -> Result<u16, Trap> {
-		match self.load_bytes(v_address, 2) {
+		match self.load_bytes(v_address, 2, &MemoryAccessType::Read) {
 			Ok(data) => Ok(data as u16),
 			Err(e) => Err(e)
 		}
@@ -345,7 +835,7 @@ impl Mmu {
 	/// # Arguments
 	/// * `v_address` Virtual address
 	pub fn load_word(&mut self, v_address: u64) -> Result<u32, Trap> {
-		match self.load_bytes(v_address, 4) {
+		match self.load_bytes(v_address, 4, &MemoryAccessType::Read) {
 			Ok(data) => Ok(data as u32),
 			Err(e) => Err(e)
 		}
@@ -357,12 +847,38 @@ impl Mmu {
 	/// # Arguments
 	/// * `v_address` Virtual address
 	pub fn load_doubleword(&mut self, v_address: u64) -> Result<u64, Trap> {
-		match self.load_bytes(v_address, 8) {
+		match self.load_bytes(v_address, 8, &MemoryAccessType::Read) {
 			Ok(data) => Ok(data as u64),
 			Err(e) => Err(e)
 		}
 	}
 
+	/// Loads two bytes from memory which must be executable instead of
+	/// readable, for HLVX.HU. This method takes virtual address and translates
+	/// into physical address inside.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	pub fn load_executable_halfword(&mut self, v_address: u64) -> Result<u16, Trap> {
+		match self.load_bytes(v_address, 2, &MemoryAccessType::ExecutableRead) {
+			Ok(data) => Ok(data as u16),
+			Err(e) => Err(e)
+		}
+	}
+
+	/// Loads four bytes from memory which must be executable instead of
+	/// readable, for HLVX.WU. This method takes virtual address and translates
+	/// into physical address inside.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	pub fn load_executable_word(&mut self, v_address: u64) -> Result<u32, Trap> {
+		match self.load_bytes(v_address, 4, &MemoryAccessType::ExecutableRead) {
+			Ok(data) => Ok(data as u32),
+			Err(e) => Err(e)
+		}
+	}
+
 	/// Store an byte. This method takes virtual address and translates
 	/// into physical address inside.
 	///
@@ -370,20 +886,19 @@ impl Mmu {
 	/// * `v_address` Virtual address
 	/// * `value`
 	pub fn store(&mut self, v_address: u64, value: u8) -> Result<(), Trap> {
-		match self.translate_address(v_address, &MemoryAccessType::Write) {
+		match self.translate_and_check_address(v_address, 1, &MemoryAccessType::Write) {
 			Ok(p_address) => {
+				self.invalidate_reservation(p_address, 1);
 				self.store_raw(p_address, value);
 				Ok(())
 			},
-			Err(()) => Err(Trap {
-				trap_type: TrapType::StorePageFault,
-				value: v_address
-			})
+			Err(e) => Err(e)
 		}
 	}
 
 	/// Stores multiple bytes. This method takes virtual address and translates
-	/// into physical address inside.
+	/// into physical address inside. Misaligned access is handled depending
+	/// on `MisalignedAccessPolicy`.
 	///
 	/// # Arguments
 	/// * `v_address` Virtual address
@@ -392,9 +907,20 @@ impl Mmu {
 	fn store_bytes(&mut self, v_address: u64, value: u64, width: u64) -> Result<(), Trap> {
 		debug_assert!(width == 1 || width == 2 || width == 4 || width == 8,
 			"Width must be 1, 2, 4, or 8. {:X}", width);
-		match (v_address & 0xfff) <= (0x1000 - width) {
-			true => match self.translate_address(v_address, &MemoryAccessType::Write) {
+		let effective_address = self.get_effective_address(v_address);
+		match self.misaligned_access_policy {
+			MisalignedAccessPolicy::Trap => if (effective_address & (width - 1)) != 0 {
+				return Err(Trap {
+					trap_type: TrapType::StoreAddressMisaligned,
+					value: effective_address
+				});
+			},
+			MisalignedAccessPolicy::Emulate => {}
+		};
+		match (effective_address & 0xfff) <= (0x1000 - width) {
+			true => match self.translate_and_check_address(effective_address, width, &MemoryAccessType::Write) {
 				Ok(p_address) => {
+					self.invalidate_reservation(p_address, width);
 					// Fast path. All bytes fetched are in the same page so
 					// translating an address only once.
 					match width {
@@ -406,17 +932,17 @@ impl Mmu {
 					}
 					Ok(())
 				},
-				Err(()) => Err(Trap {
-					trap_type: TrapType::StorePageFault,
-					value: v_address
-				})
+				Err(e) => Err(e)
 			},
 			false => {
-				for i in 0..width {
-					match self.store(v_address.wrapping_add(i), ((value >> (i * 8)) & 0xff) as u8) {
-						Ok(()) => {},
-						Err(e) => return Err(e)
-					}
+				// Nothing is stored if either page faults
+				let p_addresses = match self.translate_page_crossing_address(effective_address, width, &MemoryAccessType::Write) {
+					Ok(p_addresses) => p_addresses,
+					Err(e) => return Err(e)
+				};
+				for (i, p_address) in p_addresses.iter().take(width as usize).enumerate() {
+					self.invalidate_reservation(*p_address, 1);
+					self.store_raw(*p_address, (value >> (i * 8)) as u8);
 				}
 				Ok(())
 			}
@@ -453,71 +979,304 @@ impl Mmu {
 		self.store_bytes(v_address, value as u64, 8)
 	}
 
-	/// Loads a byte from main memory or peripheral devices depending on
-	/// physical address.
+	/// Zeroes the cache block including the address, for CBO.ZERO. It's
+	/// a store to the whole block. This method takes virtual address and
+	/// translates into physical address inside.
 	///
 	/// # Arguments
-	/// * `p_address` Physical address
-	fn load_raw(&mut self, p_address: u64) -> u8 {
-		let effective_address = self.get_effective_address(p_address);
-		// @TODO: Mapping should be configurable with dtb
-		match effective_address >= DRAM_BASE {
-			true => self.memory.read_byte(effective_address),
-			false => match effective_address {
-				// I don't know why but dtb data seems to be stored from 0x1020 on Linux.
-				// It might be from self.x[0xb] initialization?
-				// And DTB size is arbitray.
-				0x00001020..=0x00001fff => self.dtb[effective_address as usize - 0x1020],
-				0x02000000..=0x0200ffff => self.clint.load(effective_address),
-				0x0C000000..=0x0fffffff => self.plic.load(effective_address),
-				0x10000000..=0x100000ff => self.uart.load(effective_address),
-				0x10001000..=0x10001FFF => self.disk.load(effective_address),
-				_ => panic!("Unknown memory mapping {:X}.", effective_address)
-			}
+	/// * `v_address` Virtual address, any address in the block
+	pub fn zero_cache_block(&mut self, v_address: u64) -> Result<(), Trap> {
+		let effective_address = self.get_effective_address(v_address);
+		// Cache block never crosses a page boundary
+		let p_address = match self.translate_address(effective_address, &MemoryAccessType::Write) {
+			Ok(p_address) => p_address & !(self.cache_block_size - 1),
+			Err(e) => return Err(e)
+		};
+		match self.check_pmp(p_address, self.cache_block_size, &MemoryAccessType::Write, effective_address) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		self.invalidate_reservation(p_address, self.cache_block_size);
+		for offset in (0..self.cache_block_size).step_by(8) {
+			self.store_doubleword_raw(p_address.wrapping_add(offset), 0);
 		}
+		Ok(())
 	}
 
-	/// Loads two bytes from main memory or peripheral devices depending on
-	/// physical address.
+	/// Checks whether cache-block management operation, CBO.CLEAN,
+	/// CBO.FLUSH, or CBO.INVAL, can access the cache block including
+	/// the address. They are permitted if load is permitted and their
+	/// faults are reported as store ones. No cache is emulated so
+	/// nothing else is done. This method takes virtual address and
+	/// translates into physical address inside.
 	///
 	/// # Arguments
-	/// * `p_address` Physical address
-	fn load_halfword_raw(&mut self, p_address: u64) -> u16 {
-		let effective_address = self.get_effective_address(p_address);
-		match effective_address >= DRAM_BASE && effective_address.wrapping_add(1) > effective_address {
-			// Fast path. Directly load main memory at a time.
-			true => self.memory.read_halfword(effective_address),
-			false => {
-				let mut data = 0 as u16;
-				for i in 0..2 {
-					data |= (self.load_raw(effective_address.wrapping_add(i)) as u16) << (i * 8)
-				}
-				data
-			}
+	/// * `v_address` Virtual address, any address in the block
+	pub fn check_cache_block_access(&mut self, v_address: u64) -> Result<(), Trap> {
+		let effective_address = self.get_effective_address(v_address);
+		let result = match self.translate_address(effective_address, &MemoryAccessType::Read) {
+			Ok(p_address) => self.check_pmp(p_address & !(self.cache_block_size - 1),
+				self.cache_block_size, &MemoryAccessType::Read, effective_address),
+			Err(e) => Err(e)
+		};
+		match result {
+			Ok(()) => Ok(()),
+			Err(e) => Err(Trap {
+				trap_type: match e.trap_type {
+					TrapType::LoadAccessFault => TrapType::StoreAccessFault,
+					TrapType::LoadPageFault => TrapType::StorePageFault,
+					TrapType::LoadGuestPageFault => TrapType::StoreGuestPageFault,
+					trap_type => trap_type
+				},
+				value: e.value
+			})
 		}
 	}
 
-	/// Loads four bytes from main memory or peripheral devices depending on
-	/// physical address.
+	/// Loads multiple bytes and registers the reservation set for
+	/// the following store conditional. This method takes virtual address
+	/// and translates into physical address inside.
 	///
 	/// # Arguments
-	/// * `p_address` Physical address
-	pub fn load_word_raw(&mut self, p_address: u64) -> u32 {
-		let effective_address = self.get_effective_address(p_address);
-		match effective_address >= DRAM_BASE && effective_address.wrapping_add(3) > effective_address {
-			// Fast path. Directly load main memory at a time.
-			true => self.memory.read_word(effective_address),
-			false => {
-				let mut data = 0 as u32;
-				for i in 0..4 {
-					data |= (self.load_raw(effective_address.wrapping_add(i)) as u32) << (i * 8)
-				}
-				data
-			}
+	/// * `v_address` Virtual address
+	/// * `width` Must be 4 or 8
+	fn load_reserved_bytes(&mut self, v_address: u64, width: u64) -> Result<u64, Trap> {
+		if (v_address & (width - 1)) != 0 {
+			return Err(Trap {
+				trap_type: TrapType::LoadAddressMisaligned,
+				value: v_address
+			});
+		}
+		match self.translate_and_check_address(v_address, width, &MemoryAccessType::Read) {
+			Ok(p_address) => {
+				let data = match width {
+					4 => self.load_word_raw(p_address) as u64,
+					8 => self.load_doubleword_raw(p_address),
+					_ => panic!("Width must be 4 or 8. {:X}", width)
+				};
+				self.reservation = Some(self.get_effective_address(p_address) & !(RESERVATION_GRANULE - 1));
+				Ok(data)
+			},
+			Err(e) => Err(e)
 		}
 	}
 
-	/// Loads eight bytes from main memory or peripheral devices depending on
+	/// Loads four bytes with reservation, for LR.W.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	pub fn load_reserved_word(&mut self, v_address: u64) -> Result<u32, Trap> {
+		match self.load_reserved_bytes(v_address, 4) {
+			Ok(data) => Ok(data as u32),
+			Err(e) => Err(e)
+		}
+	}
+
+	/// Loads eight bytes with reservation, for LR.D.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	pub fn load_reserved_doubleword(&mut self, v_address: u64) -> Result<u64, Trap> {
+		self.load_reserved_bytes(v_address, 8)
+	}
+
+	/// Stores multiple bytes only if the reservation set registered by
+	/// the preceding load reserved still covers the address. The reservation
+	/// is invalidated regardless of the result. Returns whether the store
+	/// is done. This method takes virtual address and translates into
+	/// physical address inside.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	/// * `value` data written
+	/// * `width` Must be 4 or 8
+	fn store_conditional_bytes(&mut self, v_address: u64, value: u64, width: u64) -> Result<bool, Trap> {
+		if (v_address & (width - 1)) != 0 {
+			return Err(Trap {
+				trap_type: TrapType::StoreAddressMisaligned,
+				value: v_address
+			});
+		}
+		let p_address = match self.translate_and_check_address(v_address, width, &MemoryAccessType::Write) {
+			Ok(p_address) => p_address,
+			Err(e) => return Err(e)
+		};
+		let granule = self.get_effective_address(p_address) & !(RESERVATION_GRANULE - 1);
+		let succeeded = self.reservation == Some(granule);
+		self.reservation = None;
+		if succeeded {
+			match width {
+				4 => self.store_word_raw(p_address, value as u32),
+				8 => self.store_doubleword_raw(p_address, value),
+				_ => panic!("Width must be 4 or 8. {:X}", width)
+			};
+		}
+		Ok(succeeded)
+	}
+
+	/// Stores four bytes if reserved, for SC.W.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	/// * `value` data written
+	pub fn store_conditional_word(&mut self, v_address: u64, value: u32) -> Result<bool, Trap> {
+		self.store_conditional_bytes(v_address, value as u64, 4)
+	}
+
+	/// Stores eight bytes if reserved, for SC.D.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	/// * `value` data written
+	pub fn store_conditional_doubleword(&mut self, v_address: u64, value: u64) -> Result<bool, Trap> {
+		self.store_conditional_bytes(v_address, value, 8)
+	}
+
+	/// Atomically loads multiple bytes, applies `operation` to them, and
+	/// stores the result back. Returns the original data. Any fault,
+	/// including misaligned address, is reported as store/AMO exception.
+	/// This method takes virtual address and translates into physical
+	/// address inside.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	/// * `width` Must be 4 or 8
+	/// * `operation` Computes the data written from the original data
+	fn amo_bytes(&mut self, v_address: u64, width: u64, operation: &dyn Fn(u64) -> u64) -> Result<u64, Trap> {
+		if (v_address & (width - 1)) != 0 {
+			return Err(Trap {
+				trap_type: TrapType::StoreAddressMisaligned,
+				value: v_address
+			});
+		}
+		let p_address = match self.translate_and_check_address(v_address, width, &MemoryAccessType::Write) {
+			Ok(p_address) => p_address,
+			Err(e) => return Err(e)
+		};
+		self.invalidate_reservation(p_address, width);
+		let data = match width {
+			4 => self.load_word_raw(p_address) as u64,
+			8 => self.load_doubleword_raw(p_address),
+			_ => panic!("Width must be 4 or 8. {:X}", width)
+		};
+		match width {
+			4 => self.store_word_raw(p_address, operation(data) as u32),
+			_ => self.store_doubleword_raw(p_address, operation(data))
+		};
+		Ok(data)
+	}
+
+	/// Atomic memory operation on four bytes, for AMO*.W.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	/// * `operation` Computes the data written from the original data
+	pub fn amo_word(&mut self, v_address: u64, operation: &dyn Fn(u32) -> u32) -> Result<u32, Trap> {
+		match self.amo_bytes(v_address, 4, &|data| operation(data as u32) as u64) {
+			Ok(data) => Ok(data as u32),
+			Err(e) => Err(e)
+		}
+	}
+
+	/// Atomic memory operation on eight bytes, for AMO*.D.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	/// * `operation` Computes the data written from the original data
+	pub fn amo_doubleword(&mut self, v_address: u64, operation: &dyn Fn(u64) -> u64) -> Result<u64, Trap> {
+		self.amo_bytes(v_address, 8, operation)
+	}
+
+	/// Invalidates LR/SC reservation set. `Cpu` needs to call this method
+	/// when it takes a trap.
+	pub fn clear_reservation(&mut self) {
+		self.reservation = None;
+	}
+
+	/// Invalidates LR/SC reservation set if the store hits the reserved granule.
+	/// The store can span more than two granules, for example CBO.ZERO with
+	/// a large cache block.
+	///
+	/// # Arguments
+	/// * `p_address` Physical address
+	/// * `width` Number of bytes stored
+	fn invalidate_reservation(&mut self, p_address: u64, width: u64) {
+		let reservation = match self.reservation {
+			Some(reservation) => reservation,
+			None => return
+		};
+		let first = self.get_effective_address(p_address) & !(RESERVATION_GRANULE - 1);
+		let last = self.get_effective_address(p_address.wrapping_add(width - 1)) & !(RESERVATION_GRANULE - 1);
+		if reservation >= first && reservation <= last {
+			self.reservation = None;
+		}
+	}
+
+	/// Loads a byte from main memory or peripheral devices depending on
+	/// physical address.
+	///
+	/// # Arguments
+	/// * `p_address` Physical address
+	fn load_raw(&mut self, p_address: u64) -> u8 {
+		let effective_address = self.get_effective_address(p_address);
+		// @TODO: Mapping should be configurable with dtb
+		match effective_address >= DRAM_BASE {
+			true => self.memory.read_byte(effective_address),
+			false => match effective_address {
+				// I don't know why but dtb data seems to be stored from 0x1020 on Linux.
+				// It might be from self.x[0xb] initialization?
+				// And DTB size is arbitray.
+				0x00001020..=0x00001fff => self.dtb[effective_address as usize - 0x1020],
+				0x02000000..=0x0200ffff => self.clint.load(effective_address),
+				0x0C000000..=0x0fffffff => self.plic.load(effective_address),
+				0x10000000..=0x100000ff => self.uart.load(effective_address),
+				0x10001000..=0x10001FFF => self.disk.load(effective_address),
+				_ => panic!("Unknown memory mapping {:X}.", effective_address)
+			}
+		}
+	}
+
+	/// Loads two bytes from main memory or peripheral devices depending on
+	/// physical address.
+	///
+	/// # Arguments
+	/// * `p_address` Physical address
+	fn load_halfword_raw(&mut self, p_address: u64) -> u16 {
+		let effective_address = self.get_effective_address(p_address);
+		match effective_address >= DRAM_BASE && effective_address.wrapping_add(1) > effective_address {
+			// Fast path. Directly load main memory at a time.
+			true => self.memory.read_halfword(effective_address),
+			false => {
+				let mut data = 0 as u16;
+				for i in 0..2 {
+					data |= (self.load_raw(effective_address.wrapping_add(i)) as u16) << (i * 8)
+				}
+				data
+			}
+		}
+	}
+
+	/// Loads four bytes from main memory or peripheral devices depending on
+	/// physical address.
+	///
+	/// # Arguments
+	/// * `p_address` Physical address
+	pub fn load_word_raw(&mut self, p_address: u64) -> u32 {
+		let effective_address = self.get_effective_address(p_address);
+		match effective_address >= DRAM_BASE && effective_address.wrapping_add(3) > effective_address {
+			// Fast path. Directly load main memory at a time.
+			true => self.memory.read_word(effective_address),
+			false => {
+				let mut data = 0 as u32;
+				for i in 0..4 {
+					data |= (self.load_raw(effective_address.wrapping_add(i)) as u32) << (i * 8)
+				}
+				data
+			}
+		}
+	}
+
+	/// Loads eight bytes from main memory or peripheral devices depending on
 	/// physical address.
 	///
 	/// # Arguments
@@ -624,7 +1383,7 @@ impl Mmu {
 		// @TODO: Support other access types?
 		let p_address = match self.translate_address(v_address, &MemoryAccessType::DontCare) {
 			Ok(address) => address,
-			Err(()) => return Err(())
+			Err(_e) => return Err(())
 		};
 		let effective_address = self.get_effective_address(p_address);
 		let valid = match effective_address >= DRAM_BASE {
@@ -641,213 +1400,425 @@ impl Mmu {
 		Ok(valid)
 	}
 
-	fn translate_address(&mut self, v_address: u64, access_type: &MemoryAccessType) -> Result<u64, ()> {
-		let address = self.get_effective_address(v_address);
-		let v_page = address & !0xfff;
-		let cache = match self.page_cache_enabled {
-			true => match access_type {
-				MemoryAccessType::Execute => self.fetch_page_cache.get(&v_page),
-				MemoryAccessType::Read => self.load_page_cache.get(&v_page),
-				MemoryAccessType::Write => self.store_page_cache.get(&v_page),
-				MemoryAccessType::DontCare => None,
-			},
-			false => None
+	/// Translates virtual address into physical address and checks physical
+	/// memory protection for the access. The access fault trap is raised if
+	/// the protection doesn't permit it.
+	///
+	/// # Arguments
+	/// * `v_address` Virtual address
+	/// * `width` The number of bytes accessed
+	/// * `access_type`
+	fn translate_and_check_address(&mut self, v_address: u64, width: u64, access_type: &MemoryAccessType) -> Result<u64, Trap> {
+		let p_address = match self.translate_address(v_address, access_type) {
+			Ok(p_address) => p_address,
+			Err(e) => return Err(e)
 		};
-		match cache {
-			Some(p_page) => Ok(p_page | (address & 0xfff)),
-			None => {
-				let p_address = match self.addressing_mode {
+		match self.check_pmp(p_address, width, access_type, v_address) {
+			Ok(()) => Ok(p_address),
+			Err(e) => Err(e)
+		}
+	}
+
+	/// Checks physical memory protection for the access. The access fault
+	/// trap is raised if the protection doesn't permit it.
+	///
+	/// # Arguments
+	/// * `p_address` Physical address
+	/// * `width` The number of bytes accessed
+	/// * `access_type`
+	/// * `v_address` Virtual address stored to `tval`
+	fn check_pmp(&self, p_address: u64, width: u64, access_type: &MemoryAccessType, v_address: u64) -> Result<(), Trap> {
+		let (privilege_mode, _virtualization_mode) = self.get_effective_privilege_mode(access_type);
+		match self.pmp.check(self.get_effective_address(p_address), width, &privilege_mode, get_pmp_access(access_type)) {
+			true => Ok(()),
+			false => Err(create_access_fault(access_type, self.get_effective_address(v_address)))
+		}
+	}
+
+	/// Returns privilege mode and virtualization mode used for address
+	/// translation and memory protection. Loads and stores in machine mode
+	/// use `mstatus.MPP` and `mstatus.MPV` if `mstatus.MPRV` is set.
+	/// Hypervisor virtual-machine loads and stores use the mode specified
+	/// with `update_hypervisor_access()` in virtualization mode.
+	///
+	/// # Arguments
+	/// * `access_type`
+	fn get_effective_privilege_mode(&self, access_type: &MemoryAccessType) -> (PrivilegeMode, bool) {
+		match self.hypervisor_access {
+			Some(ref mode) => (mode.clone(), true),
+			None => match self.privilege_mode {
+				PrivilegeMode::Machine => match access_type {
+					MemoryAccessType::Execute => (PrivilegeMode::Machine, false),
+					_ => match self.mstatus & MSTATUS_MPRV {
+						0 => (PrivilegeMode::Machine, false),
+						_ => match get_privilege_mode((self.mstatus >> 11) & 3) { // MPP
+							PrivilegeMode::Machine => (PrivilegeMode::Machine, false),
+							mode => (mode, (self.mstatus & MSTATUS_MPV) != 0)
+						}
+					}
+				},
+				_ => (self.privilege_mode.clone(), self.virtualization_mode)
+			}
+		}
+	}
+
+	fn translate_address(&mut self, v_address: u64, access_type: &MemoryAccessType) -> Result<u64, Trap> {
+		let address = self.get_effective_address(v_address);
+		// Loads and stores in machine mode are translated with
+		// the privilege mode in MPP if MPRV is set
+		let (privilege_mode, virtualization_mode) = self.get_effective_privilege_mode(access_type);
+		match privilege_mode {
+			PrivilegeMode::Machine => Ok(address),
+			_ => match virtualization_mode {
+				true => self.translate_guest_address(address, access_type, &privilege_mode),
+				false => match self.addressing_mode {
 					AddressingMode::None => Ok(address),
-					AddressingMode::SV32 => match self.privilege_mode {
-						// @TODO: Optimize
-						PrivilegeMode::Machine => match access_type {
-							MemoryAccessType::Execute => Ok(address),
-							// @TODO: Remove magic number
-							_ => match (self.mstatus >> 17) & 1 {
-								0 => Ok(address),
-								_ => {
-									let privilege_mode = get_privilege_mode((self.mstatus >> 9) & 3);
-									match privilege_mode {
-										PrivilegeMode::Machine => Ok(address),
-										_ => {
-											let current_privilege_mode = self.privilege_mode.clone();
-											self.update_privilege_mode(privilege_mode);
-											let result = self.translate_address(v_address, access_type);
-											self.update_privilege_mode(current_privilege_mode);
-											result
-										}
-									}
+					_ => {
+						let key = (get_tlb_context(None, self.asid), address >> 12);
+						let p_page = match self.tlb.get(&key) {
+							Some(entry) => match self.is_cached_pte_permitted(entry.pte, access_type, &privilege_mode, self.mstatus) {
+								true => Some(entry.p_page),
+								false => None
+							},
+							None => None
+						};
+						match p_page {
+							Some(p_page) => Ok(p_page | (address & 0xfff)),
+							None => {
+								self.count_tlb_miss(access_type);
+								match self.walk_page_table(address, &TranslationStage::Single, access_type, &privilege_mode, access_type, address) {
+									Ok((p_address, pte, superpage_mask)) => {
+										self.insert_tlb_entry(key, p_address, pte, 0, superpage_mask);
+										Ok(p_address)
+									},
+									Err(e) => Err(e)
 								}
 							}
-						},
-						PrivilegeMode::User | PrivilegeMode::Supervisor => {
-							let vpns = [(address >> 12) & 0x3ff, (address >> 22) & 0x3ff];
-							self.traverse_page(address, 2 - 1, self.ppn, &vpns, &access_type)
-						},
-						_ => Ok(address)
-					},
-					AddressingMode::SV39 => match self.privilege_mode {
-						// @TODO: Optimize
-						// @TODO: Remove duplicated code with SV32
-						PrivilegeMode::Machine => match access_type {
-							MemoryAccessType::Execute => Ok(address),
-							// @TODO: Remove magic number
-							_ => match (self.mstatus >> 17) & 1 {
-								0 => Ok(address),
-								_ => {
-									let privilege_mode = get_privilege_mode((self.mstatus >> 9) & 3);
-									match privilege_mode {
-										PrivilegeMode::Machine => Ok(address),
-										_ => {
-											let current_privilege_mode = self.privilege_mode.clone();
-											self.update_privilege_mode(privilege_mode);
-											let result = self.translate_address(v_address, access_type);
-											self.update_privilege_mode(current_privilege_mode);
-											result
-										}
-									}
-								}
-							}
-						},
-						PrivilegeMode::User | PrivilegeMode::Supervisor => {
-							let vpns = [(address >> 12) & 0x1ff, (address >> 21) & 0x1ff, (address >> 30) & 0x1ff];
-							self.traverse_page(address, 3 - 1, self.ppn, &vpns, &access_type)
-						},
-						_ => Ok(address)
-					},
-					AddressingMode::SV48 => {
-						panic!("AddressingMode SV48 is not supported yet.");
+						}
 					}
+				}
+			}
+		}
+	}
+
+	/// Translates guest virtual address into physical address in
+	/// virtualization mode. VS-stage translates it into guest physical
+	/// address, and then G-stage translates it into physical address.
+	/// Either stage can be bare. TLB caches the whole translation.
+	///
+	/// # Arguments
+	/// * `address` Effective guest virtual address
+	/// * `access_type`
+	/// * `privilege_mode` Effective privilege mode, VS-mode (supervisor) or VU-mode (user)
+	fn translate_guest_address(&mut self, address: u64, access_type: &MemoryAccessType, privilege_mode: &PrivilegeMode) -> Result<u64, Trap> {
+		let key = (get_tlb_context(Some(self.vmid), self.vs_asid), address >> 12);
+		let p_page = match self.tlb.get(&key) {
+			Some(entry) => {
+				let is_vs_stage_permitted = match self.vs_addressing_mode {
+					AddressingMode::None => true,
+					_ => self.is_cached_pte_permitted(entry.pte, access_type, privilege_mode, self.get_translation_status(&TranslationStage::VirtualSupervisor))
 				};
-				match self.page_cache_enabled {
-					true => match p_address {
-						Ok(p_address) => {
-							let p_page = p_address & !0xfff;
-							match access_type {
-								MemoryAccessType::Execute => self.fetch_page_cache.insert(v_page, p_page),
-								MemoryAccessType::Read => self.load_page_cache.insert(v_page, p_page),
-								MemoryAccessType::Write => self.store_page_cache.insert(v_page, p_page),
-								MemoryAccessType::DontCare => None,
-							};
-							Ok(p_address)
-						},
-						Err(()) => Err(())
-					},
-					false => p_address
+				let is_g_stage_permitted = match self.g_addressing_mode {
+					AddressingMode::None => true,
+					_ => self.is_cached_pte_permitted(entry.g_pte, access_type, &PrivilegeMode::User, self.get_translation_status(&TranslationStage::Guest))
+				};
+				match is_vs_stage_permitted && is_g_stage_permitted {
+					true => Some(entry.p_page),
+					false => None
 				}
+			},
+			None => None
+		};
+		match p_page {
+			Some(p_page) => return Ok(p_page | (address & 0xfff)),
+			None => self.count_tlb_miss(access_type)
+		};
+		let (guest_p_address, pte, superpage_mask) = match self.vs_addressing_mode {
+			AddressingMode::None => (address, 0, 0),
+			_ => match self.walk_page_table(address, &TranslationStage::VirtualSupervisor, access_type, privilege_mode, access_type, address) {
+				Ok(result) => result,
+				Err(e) => return Err(e)
+			}
+		};
+		let (p_address, g_pte) = match self.g_addressing_mode {
+			AddressingMode::None => (guest_p_address, 0),
+			_ => match self.walk_page_table(guest_p_address, &TranslationStage::Guest, access_type, &PrivilegeMode::User, access_type, address) {
+				Ok((p_address, g_pte, _superpage_mask)) => (p_address, g_pte),
+				Err(e) => return Err(e)
+			}
+		};
+		self.insert_tlb_entry(key, p_address, pte, g_pte, superpage_mask);
+		Ok(p_address)
+	}
+
+	/// Translates guest physical address into physical address with G-stage
+	/// for the implicit access to VS-stage page table. The fault is reported
+	/// for the original access.
+	///
+	/// # Arguments
+	/// * `address` Guest physical address
+	/// * `access_type` `Read`, or `Write` for updating A and D bits
+	/// * `fault_access_type` Original access type
+	/// * `v_address` Original guest virtual address stored to `tval`
+	fn translate_guest_physical_address(&mut self, address: u64, access_type: &MemoryAccessType,
+		fault_access_type: &MemoryAccessType, v_address: u64) -> Result<u64, Trap> {
+		match self.g_addressing_mode {
+			AddressingMode::None => Ok(address),
+			_ => match self.walk_page_table(address, &TranslationStage::Guest, access_type, &PrivilegeMode::User, fault_access_type, v_address) {
+				Ok((p_address, _pte, _superpage_mask)) => Ok(p_address),
+				Err(e) => Err(e)
 			}
 		}
 	}
 
-	fn traverse_page(&mut self, v_address: u64, level: u8, parent_ppn: u64,
-		vpns: &[u64], access_type: &MemoryAccessType) -> Result<u64, ()> {
-		let pagesize = 4096;
-		let ptesize = match self.addressing_mode {
-			AddressingMode::SV32 => 4,
-			_ => 8
-		};
-		let pte_address = parent_ppn * pagesize + vpns[level as usize] * ptesize;
-		let pte = match self.addressing_mode {
-			AddressingMode::SV32 => self.load_word_raw(pte_address) as u64,
-			_ => self.load_doubleword_raw(pte_address)
-		};
-		let ppn = match self.addressing_mode {
-			AddressingMode::SV32 => (pte >> 10) & 0x3fffff,
-			_ => (pte >> 10) & 0xfffffffffff
-		};
-		let ppns = match self.addressing_mode {
-			AddressingMode::SV32 => [(pte >> 10) & 0x3ff, (pte >> 20) & 0xfff, 0 /*dummy*/],
-			AddressingMode::SV39 => [(pte >> 10) & 0x1ff, (pte >> 19) & 0x1ff, (pte >> 28) & 0x3ffffff],
-			_ => panic!() // Shouldn't happen
-		};
-		let _rsw = (pte >> 8) & 0x3;
-		let d = (pte >> 7) & 1;
-		let a = (pte >> 6) & 1;
-		let _g = (pte >> 5) & 1;
-		let _u = (pte >> 4) & 1;
-		let x = (pte >> 3) & 1;
-		let w = (pte >> 2) & 1;
-		let r = (pte >> 1) & 1;
-		let v = pte & 1;
-
-		// println!("VA:{:X} Level:{:X} PTE_AD:{:X} PTE:{:X} PPPN:{:X} PPN:{:X} PPN1:{:X} PPN0:{:X}", v_address, level, pte_address, pte, parent_ppn, ppn, ppns[1], ppns[0]);
-
-		if v == 0 || (r == 0 && w == 1) {
-			return Err(());
-		}
-
-		if r == 0 && x == 0 {
-			return match level {
-				0 => Err(()),
-				_ => self.traverse_page(v_address, level - 1, ppn, vpns, access_type)
-			};
+	/// Returns the status bits, SUM and MXR, which affect the permission
+	/// check of the translation stage. In virtualization mode, `vsstatus`
+	/// affects VS-stage, and `mstatus.MXR` affects both stages.
+	fn get_translation_status(&self, stage: &TranslationStage) -> u64 {
+		match stage {
+			TranslationStage::Single => self.mstatus,
+			TranslationStage::VirtualSupervisor => (self.vsstatus & (MSTATUS_SUM | MSTATUS_MXR)) | (self.mstatus & MSTATUS_MXR),
+			TranslationStage::Guest => self.mstatus & MSTATUS_MXR
 		}
+	}
 
-		// Leaf page found
+	/// Creates page fault trap, or guest-page fault trap for G-stage.
+	///
+	/// # Arguments
+	/// * `stage`
+	/// * `access_type`
+	/// * `v_address` Virtual address stored to `tval`
+	/// * `address` Address being translated. Guest physical address for G-stage.
+	fn create_translation_fault(&mut self, stage: &TranslationStage, access_type: &MemoryAccessType, v_address: u64, address: u64) -> Trap {
+		match stage {
+			TranslationStage::Guest => {
+				self.guest_fault_address = address;
+				create_guest_page_fault(access_type, v_address)
+			},
+			_ => create_page_fault(access_type, v_address)
+		}
+	}
 
-		if a == 0 || (match access_type { MemoryAccessType::Write => d == 0, _ => false }) {
-			let new_pte = pte | (1 << 6) | (match access_type {
-				MemoryAccessType::Write => 1 << 7,
-				_ => 0
-			});
-			match self.addressing_mode {
-				AddressingMode::SV32 => self.store_word_raw(pte_address, new_pte as u32),
-				_ => self.store_doubleword_raw(pte_address, new_pte)
-			};
+	/// Caches translation in TLB. TLB is flushed when it's full.
+	///
+	/// # Arguments
+	/// * `key` Translation context and virtual page number
+	/// * `p_address` Physical address
+	/// * `pte` Leaf page table entry, VS-stage one for guest translation
+	/// * `g_pte` Leaf page table entry of G-stage
+	/// * `superpage_mask` Virtual page number bits mapped by superpage
+	fn insert_tlb_entry(&mut self, key: (u64, u64), p_address: u64, pte: u64, g_pte: u64, superpage_mask: u64) {
+		if self.tlb.len() >= TLB_ENTRY_NUM {
+			self.tlb.clear();
 		}
+		self.tlb.insert(key, TlbEntry {
+			p_page: p_address & !0xfff,
+			pte: pte,
+			g_pte: g_pte,
+			superpage_mask: superpage_mask
+		});
+	}
 
-		match access_type {
-			MemoryAccessType::Execute => {
-				if x == 0 {
-					return Err(());
-				}
-			},
-			MemoryAccessType::Read => {
-				if r == 0 {
-					return Err(());
-				}
-			},
-			MemoryAccessType::Write => {
-				if w == 0 {
-					return Err(());
-				}
-			},
-			_ => {}
+	/// Returns whether the leaf page table entry cached in TLB permits the
+	/// access. Store to a page whose D bit is clear needs page table walk
+	/// to set the bit. Not permitted access also walks page table in case
+	/// the entry has been updated.
+	///
+	/// # Arguments
+	/// * `pte` Leaf page table entry
+	/// * `access_type`
+	/// * `privilege_mode` Effective privilege mode, user or supervisor
+	/// * `status` Status bits, SUM and MXR
+	fn is_cached_pte_permitted(&self, pte: u64, access_type: &MemoryAccessType, privilege_mode: &PrivilegeMode, status: u64) -> bool {
+		let is_dirty = match access_type {
+			MemoryAccessType::Write => (pte & PTE_D) != 0,
+			_ => true
 		};
+		is_dirty && self.is_pte_permitted(pte, access_type, privilege_mode, status)
+	}
 
-		let offset = v_address & 0xfff; // [11:0]
-		// @TODO: Optimize
-		let p_address = match self.addressing_mode {
-			AddressingMode::SV32 => match level {
-				1 => {
-					if ppns[0] != 0 {
-						return Err(());
-					}
-					(ppns[1] << 22) | (vpns[0] << 12) | offset
-				},
-				0 => (ppn << 12) | offset,
-				_ => panic!() // Shouldn't happen
-			},
-			_ => match level {
-				2 => {
-					if ppns[1] != 0 || ppns[0] != 0 {
-						return Err(());
-					}
-					(ppns[2] << 30) | (vpns[1] << 21) | (vpns[0] << 12) | offset
+	/// Translates address by walking page tables of the translation stage.
+	/// Returns the physical address, the leaf page table entry, and
+	/// the virtual page number bits mapped by superpage.
+	///
+	/// # Arguments
+	/// * `address` Effective virtual address, or guest physical address for G-stage
+	/// * `stage`
+	/// * `access_type`
+	/// * `privilege_mode` Effective privilege mode, user or supervisor. User for G-stage.
+	/// * `fault_access_type` Access type of the fault. The same as `access_type`
+	///   except for G-stage translation of VS-stage page table.
+	/// * `v_address` Virtual address stored to `tval`
+	fn walk_page_table(&mut self, address: u64, stage: &TranslationStage, access_type: &MemoryAccessType,
+		privilege_mode: &PrivilegeMode, fault_access_type: &MemoryAccessType, v_address: u64) -> Result<(u64, u64, u64), Trap> {
+		let (levels, root_ppn) = match stage {
+			TranslationStage::Single => (get_page_table_levels(&self.addressing_mode), self.ppn),
+			TranslationStage::VirtualSupervisor => (get_page_table_levels(&self.vs_addressing_mode), self.vs_ppn),
+			TranslationStage::Guest => (get_page_table_levels(&self.g_addressing_mode), self.g_ppn)
+		};
+		// Sv32 is the only two level addressing mode, whose VPN is 10 bits and PTE is 4 bytes
+		let (vpn_width, ptesize) = match levels {
+			2 => (10, 4),
+			_ => (9, 8)
+		};
+		// G-stage root page table is four times as large, and its VPN is 2 bits wider
+		let root_vpn_width = match stage {
+			TranslationStage::Guest => vpn_width + 2,
+			_ => vpn_width
+		};
+		let va_width = 12 + (levels as u64 - 1) * vpn_width + root_vpn_width;
+		let is_valid_address = match stage {
+			// Guest physical address must be zero extension
+			TranslationStage::Guest => (address >> va_width) == 0,
+			// 64-bit virtual address must be sign extension of the
+			// highest bit of the virtual address space
+			_ => levels == 2 || ((((address << (64 - va_width)) as i64) >> (64 - va_width)) as u64) == address
+		};
+		if !is_valid_address {
+			return Err(self.create_translation_fault(stage, fault_access_type, v_address, address));
+		}
+
+		let pagesize = 4096;
+		let mut level = levels - 1;
+		let mut parent_ppn = root_ppn;
+		loop {
+			let vpn_mask = match level == levels - 1 {
+				true => (1 << root_vpn_width) - 1,
+				false => (1 << vpn_width) - 1
+			};
+			let vpn = (address >> (12 + level as u64 * vpn_width)) & vpn_mask;
+			let pte_guest_address = parent_ppn * pagesize + vpn * ptesize;
+			// VS-stage page table is in guest physical address space
+			let pte_address = match stage {
+				TranslationStage::VirtualSupervisor => match self.translate_guest_physical_address(pte_guest_address,
+					&MemoryAccessType::Read, fault_access_type, v_address) {
+					Ok(p_address) => p_address,
+					Err(e) => return Err(e)
 				},
-				1 => {
-					if ppns[0] != 0 {
-						return Err(());
+				_ => pte_guest_address
+			};
+			// Page table accesses are checked by physical memory protection
+			// as supervisor mode accesses
+			if !self.pmp.check(self.get_effective_address(pte_address), ptesize, &PrivilegeMode::Supervisor, PMP_R) {
+				return Err(create_access_fault(fault_access_type, v_address));
+			}
+			let pte = match ptesize {
+				4 => self.load_word_raw(pte_address) as u64,
+				_ => self.load_doubleword_raw(pte_address)
+			};
+			let ppn = match ptesize {
+				4 => (pte >> 10) & 0x3fffff,
+				_ => (pte >> 10) & 0xfffffffffff
+			};
+			let d = (pte >> 7) & 1;
+			let a = (pte >> 6) & 1;
+			let x = (pte >> 3) & 1;
+			let w = (pte >> 2) & 1;
+			let r = (pte >> 1) & 1;
+			let v = pte & 1;
+
+			if v == 0 || (r == 0 && w == 1) {
+				return Err(self.create_translation_fault(stage, fault_access_type, v_address, address));
+			}
+
+			// Reserved bits [63:54] of 64-bit PTE must be zero
+			if ptesize == 8 && (pte >> 54) != 0 {
+				return Err(self.create_translation_fault(stage, fault_access_type, v_address, address));
+			}
+
+			if r == 0 && x == 0 {
+				match level {
+					0 => return Err(self.create_translation_fault(stage, fault_access_type, v_address, address)),
+					_ => {
+						level -= 1;
+						parent_ppn = ppn;
+						continue;
 					}
-					(ppns[2] << 30) | (ppns[1] << 21) | (vpns[0] << 12) | offset
-				},
-				0 => (ppn << 12) | offset,
-				_ => panic!() // Shouldn't happen
-			},
+				};
+			}
+
+			// Leaf page found
+
+			// Superpage must be aligned. The lower PPNs must be zero.
+			let superpage_mask = (1 << (level as u64 * vpn_width)) - 1;
+			if (ppn & superpage_mask) != 0 {
+				return Err(self.create_translation_fault(stage, fault_access_type, v_address, address));
+			}
+
+			if !self.is_pte_permitted(pte, access_type, privilege_mode, self.get_translation_status(stage)) {
+				return Err(self.create_translation_fault(stage, fault_access_type, v_address, address));
+			}
+
+			// A and D bits are updated only if the access is permitted
+			let is_write = match access_type {
+				MemoryAccessType::Write => true,
+				_ => false
+			};
+			let new_pte = pte | PTE_A | match is_write {
+				true => PTE_D,
+				false => 0
+			};
+			if a == 0 || (is_write && d == 0) {
+				// Updating VS-stage page table is a store in G-stage
+				match stage {
+					TranslationStage::VirtualSupervisor => match self.translate_guest_physical_address(pte_guest_address,
+						&MemoryAccessType::Write, fault_access_type, v_address) {
+						Ok(_p_address) => {},
+						Err(e) => return Err(e)
+					},
+					_ => {}
+				};
+				if !self.pmp.check(self.get_effective_address(pte_address), ptesize, &PrivilegeMode::Supervisor, PMP_W) {
+					return Err(create_access_fault(fault_access_type, v_address));
+				}
+				match ptesize {
+					4 => self.store_word_raw(pte_address, new_pte as u32),
+					_ => self.store_doubleword_raw(pte_address, new_pte)
+				};
+			}
+
+			// Superpage maps the lower VPNs as they are
+			let offset = address & 0xfff; // [11:0]
+			let p_address = ((ppn | ((address >> 12) & superpage_mask)) << 12) | offset;
+			return Ok((p_address, new_pte, superpage_mask));
+		}
+	}
+
+	/// Returns whether the leaf page table entry permits the access.
+	///
+	/// # Arguments
+	/// * `pte` Leaf page table entry
+	/// * `access_type`
+	/// * `privilege_mode` Effective privilege mode, user or supervisor
+	/// * `status` Status bits, SUM and MXR, of the translation stage
+	fn is_pte_permitted(&self, pte: u64, access_type: &MemoryAccessType, privilege_mode: &PrivilegeMode, status: u64) -> bool {
+		// User mode can access only user pages. Supervisor mode can't execute
+		// user pages, and can load and store them only if SUM is set.
+		let is_privilege_valid = match privilege_mode {
+			PrivilegeMode::User => (pte & PTE_U) != 0,
+			_ => (pte & PTE_U) == 0 || match access_type {
+				MemoryAccessType::Execute => false,
+				MemoryAccessType::DontCare => true,
+				_ => (status & MSTATUS_SUM) != 0
+			}
 		};
+		// MXR makes executable pages readable
+		is_privilege_valid && match access_type {
+			MemoryAccessType::Execute | MemoryAccessType::ExecutableRead => (pte & PTE_X) != 0,
+			MemoryAccessType::Read => (pte & PTE_R) != 0 || ((pte & PTE_X) != 0 && (status & MSTATUS_MXR) != 0),
+			MemoryAccessType::Write => (pte & PTE_W) != 0,
+			MemoryAccessType::DontCare => true
+		}
+	}
 
-		// println!("PA:{:X}", p_address);
-		Ok(p_address)
+	/// Returns immutable reference to `Pmp`.
+	pub fn get_pmp(&self) -> &Pmp {
+		&self.pmp
+	}
+
+	/// Returns mutable reference to `Pmp`.
+	pub fn get_mut_pmp(&mut self) -> &mut Pmp {
+		&mut self.pmp
 	}
 
 	/// Returns immutable reference to `Clint`.
@@ -860,6 +1831,11 @@ impl Mmu {
 		&mut self.clint
 	}
 
+	/// Returns immutable reference to `Plic`.
+	pub fn get_plic(&self) -> &Plic {
+		&self.plic
+	}
+
 	/// Returns mutable reference to `Uart`.
 	pub fn get_mut_uart(&mut self) -> &mut Uart {
 		&mut self.uart
@@ -933,3 +1909,513 @@ impl MemoryWrapper {
 		self.memory.validate_address(address - DRAM_BASE)
 	}
 }
+
+#[cfg(test)]
+mod test_mmu {
+	use terminal::DummyTerminal;
+	use super::*;
+
+	const PTE_V: u64 = 1;
+	// Leaf PTE with D, A, X, W, R, and V bits
+	const PTE_LEAF: u64 = 0xcf;
+
+	fn create_mmu(addressing_mode: AddressingMode) -> Mmu {
+		let mut mmu = Mmu::new(Xlen::Bit64, Box::new(DummyTerminal::new()));
+		mmu.init_memory(0x10000);
+		mmu.update_addressing_mode(addressing_mode);
+		mmu.update_ppn(DRAM_BASE >> 12);
+		mmu.update_privilege_mode(PrivilegeMode::Supervisor);
+		mmu
+	}
+
+	// Creates page tables for `v_address` from root table at DRAM_BASE
+	// to the table at `leaf_level` and returns the leaf PTE address.
+	// Each level table is placed at the next page.
+	fn create_page_tables(mmu: &mut Mmu, levels: u64, leaf_level: u64, v_address: u64) -> u64 {
+		let mut table = DRAM_BASE;
+		let mut level = levels - 1;
+		loop {
+			let pte_address = table + ((v_address >> (12 + level * 9)) & 0x1ff) * 8;
+			if level == leaf_level {
+				return pte_address;
+			}
+			let next_table = table + 0x1000;
+			mmu.store_doubleword_raw(pte_address, ((next_table >> 12) << 10) | PTE_V);
+			table = next_table;
+			level -= 1;
+		}
+	}
+
+	#[test]
+	fn sv48() {
+		let mut mmu = create_mmu(AddressingMode::SV48);
+		let v_address = 0x0000_4020_3040_5678;
+		let pte_address = create_page_tables(&mut mmu, 4, 0, v_address);
+		let p_page = DRAM_BASE + 0x8000;
+		mmu.store_doubleword_raw(pte_address, ((p_page >> 12) << 10) | PTE_LEAF);
+		match mmu.translate_address(v_address, &MemoryAccessType::Read) {
+			Ok(p_address) => assert_eq!(p_page + 0x678, p_address),
+			Err(_e) => panic!("Failed to translate")
+		};
+
+		// Not canonical address, bit 47 isn't sign extended
+		assert!(mmu.translate_address(0x0000_8020_3040_5678, &MemoryAccessType::Read).is_err(), "Non-canonical address shouldn't be translated");
+
+		// 2MiB superpage
+		let mut mmu = create_mmu(AddressingMode::SV48);
+		let pte_address = create_page_tables(&mut mmu, 4, 1, v_address);
+		mmu.store_doubleword_raw(pte_address, ((0x40000000 >> 12) << 10) | PTE_LEAF);
+		match mmu.translate_address(v_address, &MemoryAccessType::Read) {
+			Ok(p_address) => assert_eq!(0x40000000 + (v_address & 0x1fffff), p_address),
+			Err(_e) => panic!("Failed to translate")
+		};
+
+		// Misaligned superpage
+		mmu.store_doubleword_raw(pte_address, ((0x40001000 >> 12) << 10) | PTE_LEAF);
+		mmu.flush_tlb(None, None);
+		assert!(mmu.translate_address(v_address, &MemoryAccessType::Read).is_err(), "Misaligned superpage shouldn't be translated");
+	}
+
+	#[test]
+	fn sv57() {
+		let mut mmu = create_mmu(AddressingMode::SV57);
+		let v_address = 0xff81_4020_3040_5678; // bit 56 is sign extended
+		let pte_address = create_page_tables(&mut mmu, 5, 0, v_address);
+		let p_page = DRAM_BASE + 0x8000;
+		mmu.store_doubleword_raw(pte_address, ((p_page >> 12) << 10) | PTE_LEAF);
+		match mmu.translate_address(v_address, &MemoryAccessType::Write) {
+			Ok(p_address) => assert_eq!(p_page + 0x678, p_address),
+			Err(_e) => panic!("Failed to translate")
+		};
+
+		// 1GiB superpage
+		let mut mmu = create_mmu(AddressingMode::SV57);
+		let pte_address = create_page_tables(&mut mmu, 5, 2, v_address);
+		mmu.store_doubleword_raw(pte_address, ((0x80000000 >> 12) << 10) | PTE_LEAF);
+		match mmu.translate_address(v_address, &MemoryAccessType::Execute) {
+			Ok(p_address) => assert_eq!(0x80000000 + (v_address & 0x3fffffff), p_address),
+			Err(_e) => panic!("Failed to translate")
+		};
+
+		// Reserved PTE bits must be zero
+		mmu.store_doubleword_raw(pte_address, (1 << 60) | ((0x80000000 >> 12) << 10) | PTE_LEAF);
+		mmu.flush_tlb(None, None);
+		assert!(mmu.translate_address(v_address, &MemoryAccessType::Execute).is_err(), "PTE with reserved bits shouldn't be used");
+	}
+
+	fn expect_page_fault(result: Result<u64, Trap>, v_address: u64) {
+		match result {
+			Ok(_p_address) => panic!("Page fault is expected"),
+			Err(e) => {
+				match e.trap_type {
+					TrapType::InstructionPageFault | TrapType::LoadPageFault | TrapType::StorePageFault => {},
+					_ => panic!("Page fault is expected")
+				};
+				assert_eq!(v_address, e.value);
+			}
+		};
+	}
+
+	#[test]
+	fn user_page() {
+		let mut mmu = create_mmu(AddressingMode::SV39);
+		let user_address = 0x1000;
+		let kernel_address = 0x2000;
+		let pte_address = create_page_tables(&mut mmu, 3, 0, user_address);
+		mmu.store_doubleword_raw(pte_address, (((DRAM_BASE + 0x8000) >> 12) << 10) | PTE_LEAF | 0x10);
+		mmu.store_doubleword_raw(pte_address + 8, (((DRAM_BASE + 0x9000) >> 12) << 10) | PTE_LEAF);
+
+		// Supervisor mode can't access user page without SUM
+		expect_page_fault(mmu.translate_address(user_address, &MemoryAccessType::Read), user_address);
+		mmu.update_mstatus(MSTATUS_SUM);
+		assert!(mmu.translate_address(user_address, &MemoryAccessType::Write).is_ok());
+		// and can't execute user page even with SUM
+		expect_page_fault(mmu.translate_address(user_address, &MemoryAccessType::Execute), user_address);
+		assert!(mmu.translate_address(kernel_address, &MemoryAccessType::Execute).is_ok());
+
+		// User mode can access only user page
+		mmu.update_privilege_mode(PrivilegeMode::User);
+		assert!(mmu.translate_address(user_address, &MemoryAccessType::Execute).is_ok());
+		expect_page_fault(mmu.translate_address(kernel_address, &MemoryAccessType::Read), kernel_address);
+
+		// Machine mode loads and stores are translated as MPP mode if MPRV is set
+		mmu.update_privilege_mode(PrivilegeMode::Machine);
+		assert!(mmu.translate_address(kernel_address + 0x10, &MemoryAccessType::Read).is_ok());
+		mmu.update_mstatus(MSTATUS_MPRV); // MPP is user
+		expect_page_fault(mmu.translate_address(kernel_address + 0x10, &MemoryAccessType::Read), kernel_address + 0x10);
+		match mmu.translate_address(user_address + 0x10, &MemoryAccessType::Write) {
+			Ok(p_address) => assert_eq!(DRAM_BASE + 0x8010, p_address),
+			Err(_e) => panic!("Failed to translate")
+		};
+		// Instruction fetch isn't affected by MPRV
+		match mmu.translate_address(kernel_address, &MemoryAccessType::Execute) {
+			Ok(p_address) => assert_eq!(kernel_address, p_address),
+			Err(_e) => panic!("Failed to translate")
+		};
+	}
+
+	#[test]
+	fn make_executable_readable() {
+		let mut mmu = create_mmu(AddressingMode::SV39);
+		let v_address = 0x1000;
+		let pte_address = create_page_tables(&mut mmu, 3, 0, v_address);
+		// Execute-only page without A bit
+		let pte = (((DRAM_BASE + 0x8000) >> 12) << 10) | 0x9;
+		mmu.store_doubleword_raw(pte_address, pte);
+		expect_page_fault(mmu.translate_address(v_address, &MemoryAccessType::Read), v_address);
+		// A bit isn't updated by faulting access
+		assert_eq!(pte, mmu.load_doubleword_raw(pte_address));
+		mmu.update_mstatus(MSTATUS_MXR);
+		assert!(mmu.translate_address(v_address, &MemoryAccessType::Read).is_ok());
+		assert_eq!(pte | 0x40, mmu.load_doubleword_raw(pte_address));
+		expect_page_fault(mmu.translate_address(v_address, &MemoryAccessType::Write), v_address);
+	}
+
+	fn expect_translation(mmu: &mut Mmu, v_address: u64, access_type: &MemoryAccessType, p_address: u64) {
+		match mmu.translate_address(v_address, access_type) {
+			Ok(address) => assert_eq!(p_address, address),
+			Err(_e) => panic!("Failed to translate")
+		};
+	}
+
+	#[test]
+	fn tlb() {
+		let mut mmu = create_mmu(AddressingMode::SV39);
+		let v_address = 0x1000;
+		let pte_address = create_page_tables(&mut mmu, 3, 0, v_address);
+		mmu.store_doubleword_raw(pte_address, (((DRAM_BASE + 0x8000) >> 12) << 10) | PTE_LEAF);
+		expect_translation(&mut mmu, v_address + 0x123, &MemoryAccessType::Read, DRAM_BASE + 0x8123);
+
+		// Page table update is invisible until SFENCE.VMA
+		mmu.store_doubleword_raw(pte_address, (((DRAM_BASE + 0x9000) >> 12) << 10) | PTE_LEAF);
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, DRAM_BASE + 0x8000);
+		mmu.flush_tlb(Some(v_address + 0x456), None);
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, DRAM_BASE + 0x9000);
+
+		// Misses are counted for performance monitor
+		assert_eq!((0, 2), mmu.take_tlb_misses());
+		mmu.flush_tlb(None, None);
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Execute, DRAM_BASE + 0x9000);
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, DRAM_BASE + 0x9000);
+		assert_eq!((1, 0), mmu.take_tlb_misses());
+
+		// Flush by ASID
+		mmu.update_asid(1);
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, DRAM_BASE + 0x9000);
+		mmu.store_doubleword_raw(pte_address, (((DRAM_BASE + 0xa000) >> 12) << 10) | PTE_LEAF);
+		mmu.flush_tlb(None, Some(2));
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, DRAM_BASE + 0x9000);
+		mmu.flush_tlb(Some(v_address), Some(1));
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, DRAM_BASE + 0xa000);
+
+		// Global mapping isn't flushed by ASID
+		mmu.store_doubleword_raw(pte_address, (((DRAM_BASE + 0xb000) >> 12) << 10) | PTE_LEAF | PTE_G);
+		mmu.flush_tlb(None, Some(1));
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, DRAM_BASE + 0xb000);
+		mmu.store_doubleword_raw(pte_address, (((DRAM_BASE + 0xc000) >> 12) << 10) | PTE_LEAF);
+		mmu.flush_tlb(None, Some(1));
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, DRAM_BASE + 0xb000);
+		mmu.flush_tlb(None, None);
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, DRAM_BASE + 0xc000);
+	}
+
+	#[test]
+	fn tlb_superpage() {
+		let mut mmu = create_mmu(AddressingMode::SV39);
+		let v_address = 0x200000;
+		let pte_address = create_page_tables(&mut mmu, 3, 1, v_address);
+		mmu.store_doubleword_raw(pte_address, ((0x40000000 >> 12) << 10) | PTE_LEAF);
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, 0x40000000);
+		expect_translation(&mut mmu, v_address + 0x1000, &MemoryAccessType::Read, 0x40001000);
+		// Flushing an address in superpage flushes the entire superpage
+		mmu.store_doubleword_raw(pte_address, ((0x40200000 >> 12) << 10) | PTE_LEAF);
+		mmu.flush_tlb(Some(v_address + 0x1000), None);
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, 0x40200000);
+		expect_translation(&mut mmu, v_address + 0x1000, &MemoryAccessType::Read, 0x40201000);
+	}
+
+	#[test]
+	fn tlb_permission() {
+		let mut mmu = create_mmu(AddressingMode::SV39);
+		let v_address = 0x1000;
+		let pte_address = create_page_tables(&mut mmu, 3, 0, v_address);
+		// Readable and writable page without A and D bits
+		let pte = (((DRAM_BASE + 0x8000) >> 12) << 10) | 0x7;
+		mmu.store_doubleword_raw(pte_address, pte);
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Read, DRAM_BASE + 0x8000);
+		assert_eq!(pte | PTE_A, mmu.load_doubleword_raw(pte_address));
+		// Store to the cached page sets D bit
+		expect_translation(&mut mmu, v_address, &MemoryAccessType::Write, DRAM_BASE + 0x8000);
+		assert_eq!(pte | PTE_A | PTE_D, mmu.load_doubleword_raw(pte_address));
+		// Cached entry is checked with the current privilege mode
+		mmu.update_privilege_mode(PrivilegeMode::User);
+		expect_page_fault(mmu.translate_address(v_address, &MemoryAccessType::Read), v_address);
+		expect_page_fault(mmu.translate_address(v_address, &MemoryAccessType::Execute), v_address);
+	}
+
+	#[test]
+	fn misaligned_access() {
+		let mut mmu = create_mmu(AddressingMode::SV39);
+		// Maps 0x1000 and 0x2000 to not contiguous physical pages,
+		// and 0x3000 is unmapped
+		let pte_address = create_page_tables(&mut mmu, 3, 0, 0x1000);
+		mmu.store_doubleword_raw(pte_address, (((DRAM_BASE + 0x9000) >> 12) << 10) | PTE_LEAF);
+		mmu.store_doubleword_raw(pte_address + 8, (((DRAM_BASE + 0x8000) >> 12) << 10) | PTE_LEAF);
+
+		match mmu.store_word(0x1001, 0x12345678) {
+			Ok(()) => {},
+			Err(_e) => panic!("Failed to store")
+		};
+		assert_eq!(0x12345678, mmu.load_word_raw(DRAM_BASE + 0x9001));
+
+		// Crossing a page boundary
+		match mmu.store_doubleword(0x1ffd, 0x0123456789abcdef) {
+			Ok(()) => {},
+			Err(_e) => panic!("Failed to store")
+		};
+		assert_eq!(0xabcdef, mmu.load_word_raw(DRAM_BASE + 0x9ffc) >> 8);
+		assert_eq!(0x0123456789, mmu.load_doubleword_raw(DRAM_BASE + 0x8000) & 0xffffffffff);
+		match mmu.load_doubleword(0x1ffd) {
+			Ok(data) => assert_eq!(0x0123456789abcdef, data),
+			Err(_e) => panic!("Failed to load")
+		};
+
+		// Fault on the second page. Nothing is stored.
+		match mmu.store_word(0x2ffe, 0xffffffff) {
+			Ok(()) => panic!("Store shouldn't succeed"),
+			Err(e) => match e.trap_type {
+				TrapType::StorePageFault => assert_eq!(0x3000, e.value),
+				_ => panic!("Store page fault is expected")
+			}
+		};
+		assert_eq!(0, mmu.load_halfword_raw(DRAM_BASE + 0x8ffe));
+		match mmu.load_halfword(0x2fff) {
+			Ok(_data) => panic!("Load shouldn't succeed"),
+			Err(e) => match e.trap_type {
+				TrapType::LoadPageFault => assert_eq!(0x3000, e.value),
+				_ => panic!("Load page fault is expected")
+			}
+		};
+
+		// Trap policy
+		mmu.set_misaligned_access_policy(MisalignedAccessPolicy::Trap);
+		match mmu.load_word(0x1002) {
+			Ok(_data) => panic!("Load shouldn't succeed"),
+			Err(e) => match e.trap_type {
+				TrapType::LoadAddressMisaligned => assert_eq!(0x1002, e.value),
+				_ => panic!("Load address misaligned is expected")
+			}
+		};
+		match mmu.store_halfword(0x2fff, 0) {
+			Ok(()) => panic!("Store shouldn't succeed"),
+			Err(e) => match e.trap_type {
+				TrapType::StoreAddressMisaligned => assert_eq!(0x2fff, e.value),
+				_ => panic!("Store address misaligned is expected")
+			}
+		};
+		match mmu.load_doubleword(0x1008) {
+			Ok(data) => assert_eq!(mmu.load_doubleword_raw(DRAM_BASE + 0x9008), data),
+			Err(_e) => panic!("Failed to load")
+		};
+	}
+
+	#[test]
+	fn fetch_across_page_boundary() {
+		let mut mmu = create_mmu(AddressingMode::SV39);
+		// Maps 0x1000 and 0x2000 to not contiguous physical pages,
+		// and 0x3000 is unmapped
+		let pte_address = create_page_tables(&mut mmu, 3, 0, 0x1000);
+		mmu.store_doubleword_raw(pte_address, (((DRAM_BASE + 0x9000) >> 12) << 10) | PTE_LEAF);
+		mmu.store_doubleword_raw(pte_address + 8, (((DRAM_BASE + 0x8000) >> 12) << 10) | PTE_LEAF);
+
+		// "addi a0, a0, 12" across the page boundary
+		mmu.store_halfword_raw(DRAM_BASE + 0x9ffe, 0x0513);
+		mmu.store_halfword_raw(DRAM_BASE + 0x8000, 0x00c5);
+		match mmu.fetch_word(0x1ffe) {
+			Ok(word) => assert_eq!(0x00c50513, word),
+			Err(_e) => panic!("Failed to fetch")
+		};
+
+		// Compressed instruction at the end of the mapped page
+		mmu.store_halfword_raw(DRAM_BASE + 0x8ffe, 0x0001);
+		match mmu.fetch_word(0x2ffe) {
+			Ok(word) => assert_eq!(0x0001, word),
+			Err(_e) => panic!("Failed to fetch")
+		};
+
+		// Non-compressed instruction faults on the second page
+		mmu.store_halfword_raw(DRAM_BASE + 0x8ffe, 0x0513);
+		match mmu.fetch_word(0x2ffe) {
+			Ok(_word) => panic!("Fetch shouldn't succeed"),
+			Err(e) => match e.trap_type {
+				TrapType::InstructionPageFault => assert_eq!(0x3000, e.value),
+				_ => panic!("Instruction page fault is expected")
+			}
+		};
+	}
+
+	#[test]
+	fn physical_memory_protection() {
+		let mut mmu = create_mmu(AddressingMode::None);
+		// Entry 0: NAPOT 64KiB from DRAM_BASE, read and execute
+		mmu.get_mut_pmp().write_address(0, (DRAM_BASE >> 2) | 0x1fff, &Xlen::Bit64);
+		mmu.get_mut_pmp().write_config(0, 0x1d, &Xlen::Bit64);
+		match mmu.load_doubleword(DRAM_BASE + 8) {
+			Ok(_data) => {},
+			Err(_e) => panic!("Load should be permitted")
+		};
+		match mmu.fetch_word(DRAM_BASE + 8) {
+			Ok(_data) => {},
+			Err(_e) => panic!("Fetch should be permitted")
+		};
+		match mmu.store_doubleword(DRAM_BASE + 8, 0) {
+			Ok(()) => panic!("Store shouldn't be permitted"),
+			Err(e) => match e.trap_type {
+				TrapType::StoreAccessFault => assert_eq!(DRAM_BASE + 8, e.value),
+				_ => panic!("Store access fault is expected")
+			}
+		};
+		// No entry matches supervisor mode access
+		match mmu.load_word(DRAM_BASE + 0x10000) {
+			Ok(_data) => panic!("Load shouldn't be permitted"),
+			Err(e) => match e.trap_type {
+				TrapType::LoadAccessFault => assert_eq!(DRAM_BASE + 0x10000, e.value),
+				_ => panic!("Load access fault is expected")
+			}
+		};
+		// Access across the entry boundary
+		match mmu.load_doubleword(DRAM_BASE + 0xfffc) {
+			Ok(_data) => panic!("Load shouldn't be permitted"),
+			Err(e) => match e.trap_type {
+				TrapType::LoadAccessFault => {},
+				_ => panic!("Load access fault is expected")
+			}
+		};
+	}
+
+	#[test]
+	fn physical_memory_protection_on_page_table_walk() {
+		let mut mmu = create_mmu(AddressingMode::SV39);
+		let v_address = 0x4030_5678;
+		let pte_address = create_page_tables(&mut mmu, 3, 0, v_address);
+		mmu.store_doubleword_raw(pte_address, (((DRAM_BASE + 0x8000) >> 12) << 10) | PTE_LEAF);
+		// Entry 0: NA4 on the root page table entry, no permission
+		// Entry 1: NAPOT all address space, all permissions
+		let root_pte_address = DRAM_BASE + ((v_address >> 30) & 0x1ff) * 8;
+		mmu.get_mut_pmp().write_address(0, root_pte_address >> 2, &Xlen::Bit64);
+		mmu.get_mut_pmp().write_address(1, 0x3fffffffffffff, &Xlen::Bit64);
+		mmu.get_mut_pmp().write_config(0, 0x10 | (0x1f << 8), &Xlen::Bit64);
+		match mmu.fetch_word(v_address) {
+			Ok(_data) => panic!("Page table walk shouldn't be permitted"),
+			Err(e) => match e.trap_type {
+				TrapType::InstructionAccessFault => assert_eq!(v_address, e.value),
+				_ => panic!("Instruction access fault is expected")
+			}
+		};
+		mmu.get_mut_pmp().write_config(0, 0x1f << 8, &Xlen::Bit64);
+		match mmu.fetch_word(v_address) {
+			Ok(_data) => {},
+			Err(_e) => panic!("Fetch should be permitted")
+		};
+	}
+
+	fn expect_guest_page_fault(result: Result<u64, Trap>, v_address: u64) {
+		match result {
+			Ok(_p_address) => panic!("Guest-page fault is expected"),
+			Err(e) => {
+				match e.trap_type {
+					TrapType::InstructionGuestPageFault | TrapType::LoadGuestPageFault | TrapType::StoreGuestPageFault => {},
+					_ => panic!("Guest-page fault is expected")
+				};
+				assert_eq!(v_address, e.value);
+			}
+		};
+	}
+
+	#[test]
+	fn two_stage_translation() {
+		let mut mmu = create_mmu(AddressingMode::None);
+		// G-stage Sv39x4 with 16KiB root page table which maps 1GiB guest
+		// physical superpage at 0x80000000 to the same physical address
+		let g_root = DRAM_BASE + 0x4000;
+		let g_pte_address = g_root + (0x80000000 >> 30) * 8;
+		mmu.store_doubleword_raw(g_pte_address, ((0x80000000 >> 12) << 10) | PTE_LEAF | PTE_U);
+		mmu.update_guest_translation(AddressingMode::SV39, g_root >> 12, 1);
+		mmu.update_virtualization_mode(true);
+
+		// Bare VS-stage
+		expect_translation(&mut mmu, DRAM_BASE + 0x8123, &MemoryAccessType::Read, DRAM_BASE + 0x8123);
+		expect_guest_page_fault(mmu.translate_address(0x1234, &MemoryAccessType::Write), 0x1234);
+		assert_eq!(0x1234, mmu.get_guest_fault_address());
+
+		// G-stage accesses are always user-level
+		mmu.store_doubleword_raw(g_pte_address, ((0x80000000 >> 12) << 10) | PTE_LEAF);
+		mmu.flush_guest_physical_tlb(None);
+		expect_guest_page_fault(mmu.translate_address(DRAM_BASE + 0x8123, &MemoryAccessType::Read), DRAM_BASE + 0x8123);
+		assert_eq!(DRAM_BASE + 0x8123, mmu.get_guest_fault_address());
+		mmu.store_doubleword_raw(g_pte_address, ((0x80000000 >> 12) << 10) | PTE_LEAF | PTE_U);
+
+		// Sv39 VS-stage. Page tables are in guest physical address.
+		let v_address = 0x1000;
+		let pte_address = create_page_tables(&mut mmu, 3, 0, v_address);
+		mmu.store_doubleword_raw(pte_address, (((DRAM_BASE + 0x9000) >> 12) << 10) | PTE_LEAF);
+		mmu.update_vs_translation(AddressingMode::SV39, DRAM_BASE >> 12, 0);
+		expect_translation(&mut mmu, v_address + 0x123, &MemoryAccessType::Read, DRAM_BASE + 0x9123);
+		expect_page_fault(mmu.translate_address(0x5000, &MemoryAccessType::Read), 0x5000);
+
+		// Host translation isn't affected
+		mmu.update_virtualization_mode(false);
+		expect_translation(&mut mmu, v_address + 0x123, &MemoryAccessType::Read, v_address + 0x123);
+
+		// Implicit access to VS-stage page table faults as the original access
+		mmu.update_virtualization_mode(true);
+		mmu.store_doubleword_raw(g_pte_address, ((0x80000000 >> 12) << 10) | PTE_LEAF);
+		mmu.flush_guest_physical_tlb(Some(1));
+		match mmu.translate_address(v_address, &MemoryAccessType::Write) {
+			Ok(_p_address) => panic!("Guest-page fault is expected"),
+			Err(e) => match e.trap_type {
+				TrapType::StoreGuestPageFault => assert_eq!(v_address, e.value),
+				_ => panic!("Store guest-page fault is expected")
+			}
+		};
+		assert_eq!(DRAM_BASE, mmu.get_guest_fault_address() & !0xfff);
+	}
+
+	#[test]
+	fn cache_block() {
+		let mut mmu = create_mmu(AddressingMode::None);
+		mmu.update_privilege_mode(PrivilegeMode::Machine);
+		match mmu.find_dtb_property("riscv,cboz-block-size") {
+			Some(offset) => assert_eq!([0, 0, 0, 0x40], mmu.dtb[offset..offset + 4]),
+			None => panic!("Block size should be advertised")
+		};
+		mmu.set_cache_block_size(128);
+		for name in ["riscv,cbom-block-size", "riscv,cbop-block-size", "riscv,cboz-block-size"].iter() {
+			match mmu.find_dtb_property(name) {
+				Some(offset) => assert_eq!([0, 0, 0, 0x80], mmu.dtb[offset..offset + 4]),
+				None => panic!("Block size should be advertised")
+			};
+		}
+		assert!(mmu.find_dtb_property("riscv,isa").is_none(), "Only 32-bit property should be found");
+
+		let block = DRAM_BASE + 0x80;
+		for offset in 0..0x180 {
+			mmu.store_raw(DRAM_BASE + offset, 0xff);
+		}
+		assert!(mmu.zero_cache_block(block + 0x7f).is_ok());
+		assert_eq!(0xffffffffffffffff, mmu.load_doubleword_raw(block - 8));
+		assert_eq!(0, mmu.load_doubleword_raw(block));
+		assert_eq!(0, mmu.load_doubleword_raw(block + 0x78));
+		assert_eq!(0xffffffffffffffff, mmu.load_doubleword_raw(block + 0x80));
+
+		// Management operation faults as store
+		let mut mmu = create_mmu(AddressingMode::SV39);
+		match mmu.check_cache_block_access(0x1000) {
+			Ok(()) => panic!("Page fault is expected"),
+			Err(e) => match e.trap_type {
+				TrapType::StorePageFault => assert_eq!(0x1000, e.value),
+				_ => panic!("Store page fault is expected")
+			}
+		};
+	}
+}
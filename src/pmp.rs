@@ -0,0 +1,317 @@
+use cpu::{PrivilegeMode, Xlen};
+
+/// The number of PMP entries. `pmpcfg0`-`pmpcfg15` and
+/// `pmpaddr0`-`pmpaddr63` are implemented.
+pub const PMP_ENTRY_NUM: usize = 64;
+
+/// Read permission bit of PMP configuration and access type for `check()`
+pub const PMP_R: u8 = 0x1;
+
+/// Write permission bit of PMP configuration and access type for `check()`
+pub const PMP_W: u8 = 0x2;
+
+/// Execute permission bit of PMP configuration and access type for `check()`
+pub const PMP_X: u8 = 0x4;
+
+const PMP_A_SHIFT: u8 = 3;
+const PMP_L: u8 = 0x80;
+
+// Address matching modes in A field
+const PMP_A_OFF: u8 = 0;
+const PMP_A_TOR: u8 = 1;
+const PMP_A_NA4: u8 = 2;
+const PMP_A_NAPOT: u8 = 3;
+
+/// Emulates Physical Memory Protection. Holds `pmpcfg` and `pmpaddr` CSRs
+/// and checks physical memory accesses against them. Refer to the chapter
+/// 3.7 of the privileged specification for the detail.
+///
+/// Until any entry is enabled all accesses are permitted so that software
+/// which doesn't set up PMP, like xv6, can run in supervisor mode.
+pub struct Pmp {
+	configs: [u8; PMP_ENTRY_NUM],
+	addresses: [u64; PMP_ENTRY_NUM],
+
+	/// Whether any entry is enabled. Cached for fast path.
+	active: bool
+}
+
+impl Default for Pmp {
+	fn default() -> Self {
+		Pmp::new()
+	}
+}
+
+impl Pmp {
+	/// Creates a new `Pmp`. All entries are OFF.
+	pub fn new() -> Self {
+		Pmp {
+			configs: [0; PMP_ENTRY_NUM],
+			addresses: [0; PMP_ENTRY_NUM],
+			active: false
+		}
+	}
+
+	/// Returns the first entry index packed in `pmpcfg` CSR and the number
+	/// of entries in it. Returns `Err` if the CSR doesn't exist. Odd
+	/// numbered `pmpcfg` CSRs don't exist in 64-bit mode.
+	///
+	/// # Arguments
+	/// * `index` `pmpcfg` CSR number, 0-15
+	/// * `xlen`
+	fn get_config_entries(index: usize, xlen: &Xlen) -> Result<(usize, usize), ()> {
+		match xlen {
+			Xlen::Bit32 => Ok((index * 4, 4)),
+			Xlen::Bit64 => match index % 2 {
+				0 => Ok((index * 4, 8)),
+				_ => Err(())
+			}
+		}
+	}
+
+	/// Returns whether `pmpcfg` CSR exists.
+	///
+	/// # Arguments
+	/// * `index` `pmpcfg` CSR number, 0-15
+	/// * `xlen`
+	pub fn has_config(index: usize, xlen: &Xlen) -> bool {
+		Pmp::get_config_entries(index, xlen).is_ok()
+	}
+
+	/// Reads `pmpcfg` CSR. Returns zero if the CSR doesn't exist.
+	///
+	/// # Arguments
+	/// * `index` `pmpcfg` CSR number, 0-15
+	/// * `xlen`
+	pub fn read_config(&self, index: usize, xlen: &Xlen) -> u64 {
+		match Pmp::get_config_entries(index, xlen) {
+			Ok((first, num)) => {
+				let mut value = 0;
+				for i in 0..num {
+					value |= (self.configs[first + i] as u64) << (i * 8);
+				}
+				value
+			},
+			Err(()) => 0
+		}
+	}
+
+	/// Writes `pmpcfg` CSR. Locked entries are not updated. Reserved
+	/// bits are hardwired to zero, and W without R is treated as no
+	/// permission because the combination is reserved.
+	///
+	/// # Arguments
+	/// * `index` `pmpcfg` CSR number, 0-15
+	/// * `value`
+	/// * `xlen`
+	pub fn write_config(&mut self, index: usize, value: u64, xlen: &Xlen) {
+		let (first, num) = match Pmp::get_config_entries(index, xlen) {
+			Ok(entries) => entries,
+			Err(()) => return
+		};
+		for i in 0..num {
+			if (self.configs[first + i] & PMP_L) != 0 {
+				continue;
+			}
+			let mut config = ((value >> (i * 8)) as u8) & 0x9f;
+			if (config & (PMP_R | PMP_W)) == PMP_W {
+				config &= !PMP_W;
+			}
+			self.configs[first + i] = config;
+		}
+		self.active = self.configs.iter().any(|config| get_address_matching(*config) != PMP_A_OFF);
+	}
+
+	/// Reads `pmpaddr` CSR.
+	///
+	/// # Arguments
+	/// * `index` `pmpaddr` CSR number, 0-63
+	pub fn read_address(&self, index: usize) -> u64 {
+		self.addresses[index]
+	}
+
+	/// Writes `pmpaddr` CSR. The write is ignored if the entry is locked,
+	/// or if the next entry is locked TOR because this address is used
+	/// as its bottom.
+	///
+	/// # Arguments
+	/// * `index` `pmpaddr` CSR number, 0-63
+	/// * `value`
+	/// * `xlen`
+	pub fn write_address(&mut self, index: usize, value: u64, xlen: &Xlen) {
+		if (self.configs[index] & PMP_L) != 0 {
+			return;
+		}
+		if index + 1 < PMP_ENTRY_NUM {
+			let next = self.configs[index + 1];
+			if (next & PMP_L) != 0 && get_address_matching(next) == PMP_A_TOR {
+				return;
+			}
+		}
+		// pmpaddr holds physical address bits [33:2] in 32-bit mode
+		// and [55:2] in 64-bit mode.
+		self.addresses[index] = match xlen {
+			Xlen::Bit32 => value & 0xffffffff,
+			Xlen::Bit64 => value & 0x3fffffffffffff
+		};
+	}
+
+	/// Returns the first and the last (inclusive) byte addresses the entry
+	/// covers, or `None` if the entry matches nothing.
+	///
+	/// # Arguments
+	/// * `index` Entry index
+	fn get_range(&self, index: usize) -> Option<(u64, u64)> {
+		let address = self.addresses[index];
+		match get_address_matching(self.configs[index]) {
+			PMP_A_TOR => {
+				let bottom = match index {
+					0 => 0,
+					_ => self.addresses[index - 1] << 2
+				};
+				let top = address << 2;
+				match bottom < top {
+					true => Some((bottom, top - 1)),
+					false => None
+				}
+			},
+			PMP_A_NA4 => Some((address << 2, (address << 2) + 3)),
+			PMP_A_NAPOT => {
+				// The number of trailing ones encodes the region size,
+				// 2^(trailing ones + 3) bytes.
+				let size_width = (!address).trailing_zeros() + 3;
+				match size_width >= 64 {
+					true => Some((0, u64::MAX)),
+					false => {
+						let mask = (1u64 << size_width) - 1;
+						let first = (address << 2) & !mask;
+						Some((first, first | mask))
+					}
+				}
+			},
+			_ => None
+		}
+	}
+
+	/// Checks whether the physical memory access is permitted. The lowest
+	/// numbered entry matching any byte of the access determines the result.
+	/// The access fails if the entry doesn't cover all the bytes. Machine
+	/// mode access succeeds unless the matching entry is locked.
+	///
+	/// # Arguments
+	/// * `p_address` Physical address
+	/// * `width` The number of bytes accessed
+	/// * `privilege_mode` Effective privilege mode of the access
+	/// * `access` `PMP_R`, `PMP_W`, or `PMP_X`
+	pub fn check(&self, p_address: u64, width: u64, privilege_mode: &PrivilegeMode, access: u8) -> bool {
+		if !self.active {
+			return true;
+		}
+		let last_address = p_address.wrapping_add(width - 1);
+		for i in 0..PMP_ENTRY_NUM {
+			let (first, last) = match self.get_range(i) {
+				Some(range) => range,
+				None => continue
+			};
+			if last_address < first || p_address > last {
+				continue;
+			}
+			if p_address < first || last_address > last {
+				// Partially matched
+				return false;
+			}
+			let config = self.configs[i];
+			return match privilege_mode {
+				PrivilegeMode::Machine => (config & PMP_L) == 0 || (config & access) != 0,
+				_ => (config & access) != 0
+			};
+		}
+		matches!(privilege_mode, PrivilegeMode::Machine)
+	}
+}
+
+fn get_address_matching(config: u8) -> u8 {
+	(config >> PMP_A_SHIFT) & 0x3
+}
+
+#[cfg(test)]
+mod test_pmp {
+	use super::*;
+
+	const NAPOT: u64 = (PMP_A_NAPOT as u64) << PMP_A_SHIFT;
+	const TOR: u64 = (PMP_A_TOR as u64) << PMP_A_SHIFT;
+	const NA4: u64 = (PMP_A_NA4 as u64) << PMP_A_SHIFT;
+	const R: u64 = PMP_R as u64;
+	const W: u64 = PMP_W as u64;
+	const X: u64 = PMP_X as u64;
+	const L: u64 = PMP_L as u64;
+
+	#[test]
+	fn inactive() {
+		let pmp = Pmp::new();
+		assert!(pmp.check(0x80000000, 8, &PrivilegeMode::Supervisor, PMP_W));
+		assert!(pmp.check(0x80000000, 8, &PrivilegeMode::User, PMP_X));
+	}
+
+	#[test]
+	fn address_matching() {
+		let mut pmp = Pmp::new();
+		let xlen = Xlen::Bit64;
+		// Entry 0: TOR [0x80000000, 0x80001000) read-only
+		pmp.write_address(0, 0x80001000 >> 2, &xlen);
+		pmp.write_config(0, TOR | R, &xlen);
+		// Entry 1: NA4 0x90000000 write-only, W without R is cleared
+		pmp.write_address(1, 0x90000000 >> 2, &xlen);
+		// Entry 2: NAPOT 0x80000000-0x8000ffff all permissions
+		pmp.write_address(2, (0x80000000 >> 2) | 0x1fff, &xlen);
+		pmp.write_config(0, TOR | R | ((NA4 | W) << 8) | ((NAPOT | R | W | X) << 16), &xlen);
+		assert_eq!(TOR | R | (NA4 << 8) | ((NAPOT | R | W | X) << 16), pmp.read_config(0, &xlen));
+
+		let s = PrivilegeMode::Supervisor;
+		assert!(pmp.check(0x80000ff8, 8, &s, PMP_R));
+		// The lowest numbered entry has priority
+		assert!(!pmp.check(0x80000ff8, 8, &s, PMP_W));
+		assert!(pmp.check(0x80001000, 8, &s, PMP_W));
+		assert!(pmp.check(0x8000fffc, 4, &s, PMP_X));
+		// Partially matched access fails
+		assert!(!pmp.check(0x80000ffc, 8, &s, PMP_R));
+		assert!(!pmp.check(0x8000fffc, 8, &s, PMP_R));
+		// NA4 without permission
+		assert!(!pmp.check(0x90000000, 4, &s, PMP_W));
+		// No entry matches
+		assert!(!pmp.check(0x80010000, 4, &PrivilegeMode::User, PMP_R));
+		assert!(pmp.check(0x80010000, 4, &PrivilegeMode::Machine, PMP_R));
+		// Machine mode ignores unlocked entries
+		assert!(pmp.check(0x80000000, 4, &PrivilegeMode::Machine, PMP_W));
+	}
+
+	#[test]
+	fn lock() {
+		let mut pmp = Pmp::new();
+		let xlen = Xlen::Bit64;
+		pmp.write_address(0, 0x80000000 >> 2, &xlen);
+		pmp.write_address(1, 0x80001000 >> 2, &xlen);
+		pmp.write_config(0, (TOR | L | X) << 8, &xlen);
+		// Locked entry applies to machine mode
+		assert!(pmp.check(0x80000000, 4, &PrivilegeMode::Machine, PMP_X));
+		assert!(!pmp.check(0x80000000, 4, &PrivilegeMode::Machine, PMP_W));
+		// Locked entry and the bottom of locked TOR can't be updated
+		pmp.write_config(0, (NAPOT | R) | ((NAPOT | R | W | X) << 8), &xlen);
+		assert_eq!((NAPOT | R) | ((TOR | L | X) << 8), pmp.read_config(0, &xlen));
+		pmp.write_address(1, 0, &xlen);
+		pmp.write_address(0, 0, &xlen);
+		assert_eq!(0x80001000 >> 2, pmp.read_address(1));
+		assert_eq!(0x80000000 >> 2, pmp.read_address(0));
+	}
+
+	#[test]
+	fn config_32bit() {
+		let mut pmp = Pmp::new();
+		assert!(Pmp::has_config(1, &Xlen::Bit32));
+		assert!(!Pmp::has_config(1, &Xlen::Bit64));
+		pmp.write_config(1, (NAPOT | R) << 24, &Xlen::Bit32);
+		assert_eq!((NAPOT | R) << 24, pmp.read_config(1, &Xlen::Bit32));
+		// pmpcfg1 in 32-bit mode is the upper half of pmpcfg0 in 64-bit mode
+		assert_eq!((NAPOT | R) << 56, pmp.read_config(0, &Xlen::Bit64));
+	}
+}
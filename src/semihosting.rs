@@ -0,0 +1,658 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use cpu::Xlen;
+use mmu::Mmu;
+
+/// `slli x0, x0, 0x1f`. Placed just before `EBREAK` of semihosting call.
+pub const SEMIHOSTING_ENTRY: u32 = 0x01f01013;
+
+/// `srai x0, x0, 7`. Placed just after `EBREAK` of semihosting call.
+pub const SEMIHOSTING_EXIT: u32 = 0x40705013;
+
+const SYS_OPEN: u64 = 0x01;
+const SYS_CLOSE: u64 = 0x02;
+const SYS_WRITEC: u64 = 0x03;
+const SYS_WRITE0: u64 = 0x04;
+const SYS_WRITE: u64 = 0x05;
+const SYS_READ: u64 = 0x06;
+const SYS_CLOCK: u64 = 0x10;
+const SYS_TIME: u64 = 0x11;
+const SYS_GET_CMDLINE: u64 = 0x15;
+const SYS_EXIT: u64 = 0x18;
+const SYS_EXIT_EXTENDED: u64 = 0x20;
+
+/// `SYS_EXIT` reason meaning the program finished normally
+const ADP_STOPPED_APPLICATION_EXIT: u64 = 0x20026;
+
+/// Special file name for the console in `SYS_OPEN`
+const CONSOLE_NAME: &str = ":tt";
+
+enum Handle {
+	Console,
+	File(File)
+}
+
+/// Emulates the host side of [RISC-V semihosting](https://github.com/riscv/riscv-semihosting-spec).
+/// A program requests an operation with `a0` and passes a pointer to the
+/// parameter block, or a parameter itself, with `a1`. The result is returned
+/// in `a0`. Files are opened only under the root directory passed to `new()`
+/// and the console is connected to [`Terminal`](../terminal/trait.Terminal.html).
+pub struct Semihosting {
+	root: PathBuf,
+	command_line: String,
+	handles: Vec<Option<Handle>>,
+	start: Instant,
+	exit_code: Option<i32>
+}
+
+impl Semihosting {
+	/// Creates a new `Semihosting`.
+	///
+	/// # Arguments
+	/// * `root` Host directory which `SYS_OPEN` is sandboxed in
+	/// * `command_line` Returned by `SYS_GET_CMDLINE`
+	pub fn new(root: PathBuf, command_line: String) -> Self {
+		Semihosting {
+			root,
+			command_line,
+			handles: vec![],
+			start: Instant::now(),
+			exit_code: None
+		}
+	}
+
+	/// Returns the exit code if the program has exited with `SYS_EXIT`.
+	pub fn get_exit_code(&self) -> Option<i32> {
+		self.exit_code
+	}
+
+	/// Handles a semihosting call and returns the result written to `a0`.
+	/// Unknown or failed operation returns -1.
+	///
+	/// # Arguments
+	/// * `mmu`
+	/// * `xlen`
+	/// * `operation` `a0` content
+	/// * `parameter` `a1` content
+	pub fn call(&mut self, mmu: &mut Mmu, xlen: &Xlen, operation: u64, parameter: u64) -> i64 {
+		let result = match operation {
+			SYS_OPEN => self.open(mmu, xlen, parameter),
+			SYS_CLOSE => self.close(mmu, xlen, parameter),
+			SYS_WRITEC => match mmu.load(parameter) {
+				Ok(value) => {
+					mmu.get_mut_uart().get_mut_terminal().put_byte(value);
+					Ok(0)
+				},
+				Err(_e) => Err(())
+			},
+			SYS_WRITE0 => {
+				let mut address = parameter;
+				loop {
+					match mmu.load(address) {
+						Ok(0) => break Ok(0),
+						Ok(value) => mmu.get_mut_uart().get_mut_terminal().put_byte(value),
+						Err(_e) => break Err(())
+					};
+					address = address.wrapping_add(1);
+				}
+			},
+			SYS_WRITE => self.write(mmu, xlen, parameter),
+			SYS_READ => self.read(mmu, xlen, parameter),
+			SYS_CLOCK => Ok((self.start.elapsed().as_millis() / 10) as i64), // centiseconds
+			SYS_TIME => match SystemTime::now().duration_since(UNIX_EPOCH) {
+				Ok(duration) => Ok(duration.as_secs() as i64),
+				Err(_e) => Err(())
+			},
+			SYS_GET_CMDLINE => self.get_command_line(mmu, xlen, parameter),
+			SYS_EXIT | SYS_EXIT_EXTENDED => {
+				// 32-bit SYS_EXIT takes the reason itself and can't pass the exit code
+				let (reason, subcode) = match (operation, xlen) {
+					(SYS_EXIT, Xlen::Bit32) => (parameter, 0),
+					_ => match (read_field(mmu, xlen, parameter, 0), read_field(mmu, xlen, parameter, 1)) {
+						(Ok(reason), Ok(subcode)) => (reason, subcode),
+						_ => (0, 1)
+					}
+				};
+				self.exit_code = Some(match reason == ADP_STOPPED_APPLICATION_EXIT {
+					true => subcode as i32,
+					false => 1
+				});
+				Ok(0)
+			},
+			_ => Err(())
+		};
+		result.unwrap_or(-1)
+	}
+
+	/// `SYS_OPEN`. Parameter block is file name address, mode, and file name length.
+	fn open(&mut self, mmu: &mut Mmu, xlen: &Xlen, parameter: u64) -> Result<i64, ()> {
+		let (address, mode, length) = match (
+			read_field(mmu, xlen, parameter, 0),
+			read_field(mmu, xlen, parameter, 1),
+			read_field(mmu, xlen, parameter, 2)
+		) {
+			(Ok(address), Ok(mode), Ok(length)) => (address, mode, length),
+			_ => return Err(())
+		};
+		let name = match read_bytes(mmu, address, length) {
+			Ok(bytes) => match String::from_utf8(bytes) {
+				Ok(name) => name,
+				Err(_e) => return Err(())
+			},
+			Err(()) => return Err(())
+		};
+		let handle = match name.as_str() {
+			CONSOLE_NAME => Handle::Console,
+			_ => {
+				let path = match self.resolve_path(&name) {
+					Some(path) => path,
+					None => return Err(())
+				};
+				// Mode is fopen() mode, r, rb, r+, r+b, w, wb, w+, w+b, a, ab, a+, or a+b
+				let mut options = OpenOptions::new();
+				match mode >> 1 {
+					0 => options.read(true),
+					1 => options.read(true).write(true),
+					2 => options.write(true).create(true).truncate(true),
+					3 => options.read(true).write(true).create(true).truncate(true),
+					4 => options.append(true).create(true),
+					5 => options.read(true).append(true).create(true),
+					_ => return Err(())
+				};
+				match options.open(path) {
+					Ok(file) => Handle::File(file),
+					Err(_e) => return Err(())
+				}
+			}
+		};
+		// Handle is non-zero
+		match self.handles.iter().position(|handle| handle.is_none()) {
+			Some(index) => {
+				self.handles[index] = Some(handle);
+				Ok(index as i64 + 1)
+			},
+			None => {
+				self.handles.push(Some(handle));
+				Ok(self.handles.len() as i64)
+			}
+		}
+	}
+
+	/// `SYS_CLOSE`. Parameter block is handle.
+	fn close(&mut self, mmu: &mut Mmu, xlen: &Xlen, parameter: u64) -> Result<i64, ()> {
+		let index = match read_field(mmu, xlen, parameter, 0) {
+			Ok(handle) => match self.get_index(handle) {
+				Some(index) => index,
+				None => return Err(())
+			},
+			Err(()) => return Err(())
+		};
+		self.handles[index] = None;
+		Ok(0)
+	}
+
+	/// `SYS_WRITE`. Parameter block is handle, buffer address, and length.
+	/// Returns the number of bytes not written.
+	fn write(&mut self, mmu: &mut Mmu, xlen: &Xlen, parameter: u64) -> Result<i64, ()> {
+		let (handle, address, length) = match (
+			read_field(mmu, xlen, parameter, 0),
+			read_field(mmu, xlen, parameter, 1),
+			read_field(mmu, xlen, parameter, 2)
+		) {
+			(Ok(handle), Ok(address), Ok(length)) => (handle, address, length),
+			_ => return Err(())
+		};
+		let index = match self.get_index(handle) {
+			Some(index) => index,
+			None => return Err(())
+		};
+		let data = match read_bytes(mmu, address, length) {
+			Ok(data) => data,
+			Err(()) => return Err(())
+		};
+		match self.handles[index] {
+			Some(Handle::Console) => {
+				for value in data.iter() {
+					mmu.get_mut_uart().get_mut_terminal().put_byte(*value);
+				}
+				Ok(0)
+			},
+			Some(Handle::File(ref mut file)) => match file.write_all(&data) {
+				Ok(()) => Ok(0),
+				Err(_e) => Ok(length as i64)
+			},
+			None => Err(())
+		}
+	}
+
+	/// `SYS_READ`. Parameter block is handle, buffer address, and length.
+	/// Returns the number of bytes not read. Reading from the console
+	/// doesn't block and reads only the bytes already input.
+	fn read(&mut self, mmu: &mut Mmu, xlen: &Xlen, parameter: u64) -> Result<i64, ()> {
+		let (handle, address, length) = match (
+			read_field(mmu, xlen, parameter, 0),
+			read_field(mmu, xlen, parameter, 1),
+			read_field(mmu, xlen, parameter, 2)
+		) {
+			(Ok(handle), Ok(address), Ok(length)) => (handle, address, length),
+			_ => return Err(())
+		};
+		let index = match self.get_index(handle) {
+			Some(index) => index,
+			None => return Err(())
+		};
+		let mut data = vec![];
+		match self.handles[index] {
+			Some(Handle::Console) => {
+				while (data.len() as u64) < length {
+					match mmu.get_mut_uart().get_mut_terminal().get_input() {
+						0 => break,
+						value => data.push(value)
+					};
+				}
+			},
+			Some(Handle::File(ref mut file)) => {
+				match file.take(length).read_to_end(&mut data) {
+					Ok(_size) => {},
+					Err(_e) => return Err(())
+				};
+			},
+			None => return Err(())
+		};
+		match write_bytes(mmu, address, &data) {
+			Ok(()) => Ok((length - data.len() as u64) as i64),
+			Err(()) => Err(())
+		}
+	}
+
+	/// `SYS_GET_CMDLINE`. Parameter block is buffer address and length.
+	/// The length is updated to the command line length.
+	fn get_command_line(&mut self, mmu: &mut Mmu, xlen: &Xlen, parameter: u64) -> Result<i64, ()> {
+		let (address, length) = match (
+			read_field(mmu, xlen, parameter, 0),
+			read_field(mmu, xlen, parameter, 1)
+		) {
+			(Ok(address), Ok(length)) => (address, length),
+			_ => return Err(())
+		};
+		let mut data = self.command_line.as_bytes().to_vec();
+		// Including null terminator
+		if data.len() as u64 >= length {
+			return Err(());
+		}
+		let command_line_length = data.len() as u64;
+		data.push(0);
+		match write_bytes(mmu, address, &data) {
+			Ok(()) => {},
+			Err(()) => return Err(())
+		};
+		match write_field(mmu, xlen, parameter, 1, command_line_length) {
+			Ok(()) => Ok(0),
+			Err(()) => Err(())
+		}
+	}
+
+	/// Converts a handle to the index of `handles`. Returns `None` if the
+	/// handle isn't open.
+	fn get_index(&self, handle: u64) -> Option<usize> {
+		let index = handle.wrapping_sub(1) as usize;
+		match index < self.handles.len() && self.handles[index].is_some() {
+			true => Some(index),
+			false => None
+		}
+	}
+
+	/// Converts a file name passed by the program to the host path under
+	/// the root directory. Returns `None` if the name can point outside of
+	/// the root directory, absolute path, including `..`, or via symbolic link.
+	fn resolve_path(&self, name: &str) -> Option<PathBuf> {
+		let path = Path::new(name);
+		for component in path.components() {
+			match component {
+				Component::Normal(_) | Component::CurDir => {},
+				_ => return None
+			};
+		}
+		let root = match self.root.canonicalize() {
+			Ok(root) => root,
+			Err(_e) => return None
+		};
+		let path = root.join(path);
+		// Existing file or link is resolved with the links followed. File
+		// to be created is resolved with its parent directory.
+		let resolved = match path.symlink_metadata() {
+			Ok(_metadata) => path.canonicalize(),
+			Err(_e) => match (path.parent(), path.file_name()) {
+				(Some(parent), Some(file_name)) => match parent.canonicalize() {
+					Ok(parent) => Ok(parent.join(file_name)),
+					Err(e) => Err(e)
+				},
+				_ => return None
+			}
+		};
+		match resolved {
+			Ok(resolved) => match resolved.starts_with(&root) {
+				true => Some(resolved),
+				false => None
+			},
+			Err(_e) => None
+		}
+	}
+}
+
+/// Reads an XLEN-wide field of parameter block.
+///
+/// # Arguments
+/// * `mmu`
+/// * `xlen`
+/// * `parameter` Parameter block address
+/// * `index` Field index
+fn read_field(mmu: &mut Mmu, xlen: &Xlen, parameter: u64, index: u64) -> Result<u64, ()> {
+	let result = match xlen {
+		Xlen::Bit32 => match mmu.load_word(parameter.wrapping_add(index * 4)) {
+			Ok(data) => Ok(data as u64),
+			Err(e) => Err(e)
+		},
+		Xlen::Bit64 => mmu.load_doubleword(parameter.wrapping_add(index * 8))
+	};
+	match result {
+		Ok(data) => Ok(data),
+		Err(_e) => Err(())
+	}
+}
+
+/// Writes an XLEN-wide field of parameter block.
+///
+/// # Arguments
+/// * `mmu`
+/// * `xlen`
+/// * `parameter` Parameter block address
+/// * `index` Field index
+/// * `value`
+fn write_field(mmu: &mut Mmu, xlen: &Xlen, parameter: u64, index: u64, value: u64) -> Result<(), ()> {
+	let result = match xlen {
+		Xlen::Bit32 => mmu.store_word(parameter.wrapping_add(index * 4), value as u32),
+		Xlen::Bit64 => mmu.store_doubleword(parameter.wrapping_add(index * 8), value)
+	};
+	match result {
+		Ok(()) => Ok(()),
+		Err(_e) => Err(())
+	}
+}
+
+fn read_bytes(mmu: &mut Mmu, address: u64, length: u64) -> Result<Vec<u8>, ()> {
+	let mut data = vec![];
+	for i in 0..length {
+		match mmu.load(address.wrapping_add(i)) {
+			Ok(value) => data.push(value),
+			Err(_e) => return Err(())
+		};
+	}
+	Ok(data)
+}
+
+fn write_bytes(mmu: &mut Mmu, address: u64, data: &[u8]) -> Result<(), ()> {
+	for (i, value) in data.iter().enumerate() {
+		match mmu.store(address.wrapping_add(i as u64), *value) {
+			Ok(()) => {},
+			Err(_e) => return Err(())
+		};
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod test_semihosting {
+	use std::fs;
+	use cpu::{Cpu, Xlen};
+	use default_terminal::DefaultTerminal;
+	use mmu::DRAM_BASE;
+	use super::*;
+
+	const PARAMETER_ADDRESS: u64 = DRAM_BASE + 0x100;
+	const STRING_ADDRESS: u64 = DRAM_BASE + 0x200;
+	const BUFFER_ADDRESS: u64 = DRAM_BASE + 0x300;
+
+	fn create_cpu(root: PathBuf) -> Cpu {
+		let mut cpu = Cpu::new(Box::new(DefaultTerminal::new()));
+		cpu.get_mut_mmu().init_memory(0x1000);
+		cpu.enable_semihosting(root, "program arg".to_string());
+		cpu
+	}
+
+	fn create_root(name: &str) -> PathBuf {
+		let root = std::env::temp_dir().join(format!("riscv_emu_rust_{}_{}", name, std::process::id()));
+		match fs::create_dir_all(&root) {
+			Ok(()) => {},
+			Err(_e) => panic!("Failed to create directory")
+		};
+		root
+	}
+
+	// Runs semihosting call sequence and returns a0
+	fn call(cpu: &mut Cpu, operation: u64, parameter: u64) -> i64 {
+		let words = [SEMIHOSTING_ENTRY, 0x00100073, SEMIHOSTING_EXIT];
+		for (i, word) in words.iter().enumerate() {
+			match cpu.get_mut_mmu().store_word(DRAM_BASE + i as u64 * 4, *word) {
+				Ok(()) => {},
+				Err(_e) => panic!("Failed to store")
+			};
+		}
+		cpu.update_pc(DRAM_BASE);
+		cpu.write_register(10, operation as i64);
+		cpu.write_register(11, parameter as i64);
+		for _i in 0..words.len() {
+			cpu.tick();
+		}
+		assert_eq!(DRAM_BASE + 12, cpu.read_pc());
+		cpu.read_register(10)
+	}
+
+	fn store_parameters(cpu: &mut Cpu, parameters: &[u64]) {
+		for (i, parameter) in parameters.iter().enumerate() {
+			match cpu.get_mut_mmu().store_doubleword(PARAMETER_ADDRESS + i as u64 * 8, *parameter) {
+				Ok(()) => {},
+				Err(_e) => panic!("Failed to store")
+			};
+		}
+	}
+
+	fn store_bytes(cpu: &mut Cpu, address: u64, data: &[u8]) {
+		for (i, value) in data.iter().enumerate() {
+			match cpu.get_mut_mmu().store(address + i as u64, *value) {
+				Ok(()) => {},
+				Err(_e) => panic!("Failed to store")
+			};
+		}
+	}
+
+	fn load_bytes(cpu: &mut Cpu, address: u64, length: usize) -> Vec<u8> {
+		let mut data = vec![];
+		for i in 0..length {
+			match cpu.get_mut_mmu().load(address + i as u64) {
+				Ok(value) => data.push(value),
+				Err(_e) => panic!("Failed to load")
+			};
+		}
+		data
+	}
+
+	fn get_output(cpu: &mut Cpu) -> Vec<u8> {
+		let mut data = vec![];
+		loop {
+			match cpu.get_mut_terminal().get_output() {
+				0 => return data,
+				value => data.push(value)
+			};
+		}
+	}
+
+	// Opens file and returns the handle
+	fn open(cpu: &mut Cpu, name: &str, mode: u64) -> i64 {
+		store_bytes(cpu, STRING_ADDRESS, name.as_bytes());
+		store_parameters(cpu, &[STRING_ADDRESS, mode, name.len() as u64]);
+		call(cpu, SYS_OPEN, PARAMETER_ADDRESS)
+	}
+
+	#[test]
+	fn console() {
+		let mut cpu = create_cpu(std::env::temp_dir());
+		store_bytes(&mut cpu, STRING_ADDRESS, b"Hello\0");
+		assert_eq!(0, call(&mut cpu, SYS_WRITEC, STRING_ADDRESS));
+		assert_eq!(0, call(&mut cpu, SYS_WRITE0, STRING_ADDRESS));
+		assert_eq!(b"HHello".to_vec(), get_output(&mut cpu));
+
+		let handle = open(&mut cpu, ":tt", 4);
+		assert!(handle > 0);
+		store_bytes(&mut cpu, BUFFER_ADDRESS, b"Hello");
+		store_parameters(&mut cpu, &[handle as u64, BUFFER_ADDRESS, 3]);
+		assert_eq!(0, call(&mut cpu, SYS_WRITE, PARAMETER_ADDRESS));
+		assert_eq!(b"Hel".to_vec(), get_output(&mut cpu));
+
+		// Reading only the bytes already input
+		cpu.get_mut_terminal().put_input(b'a');
+		store_parameters(&mut cpu, &[handle as u64, BUFFER_ADDRESS, 4]);
+		assert_eq!(3, call(&mut cpu, SYS_READ, PARAMETER_ADDRESS));
+		assert_eq!(b"a".to_vec(), load_bytes(&mut cpu, BUFFER_ADDRESS, 1));
+	}
+
+	#[test]
+	fn file() {
+		let root = create_root("semihosting_file");
+		let mut cpu = create_cpu(root.clone());
+
+		// Write
+		let handle = open(&mut cpu, "test.txt", 4);
+		assert!(handle > 0);
+		store_bytes(&mut cpu, BUFFER_ADDRESS, b"hello");
+		store_parameters(&mut cpu, &[handle as u64, BUFFER_ADDRESS, 5]);
+		assert_eq!(0, call(&mut cpu, SYS_WRITE, PARAMETER_ADDRESS));
+		store_parameters(&mut cpu, &[handle as u64]);
+		assert_eq!(0, call(&mut cpu, SYS_CLOSE, PARAMETER_ADDRESS));
+		// Closed handle is invalid
+		assert_eq!(-1, call(&mut cpu, SYS_CLOSE, PARAMETER_ADDRESS));
+		match fs::read(root.join("test.txt")) {
+			Ok(data) => assert_eq!(b"hello".to_vec(), data),
+			Err(_e) => panic!("Failed to read")
+		};
+
+		// Read. Returns the number of bytes not read
+		let handle = open(&mut cpu, "test.txt", 0);
+		assert!(handle > 0);
+		store_parameters(&mut cpu, &[handle as u64, BUFFER_ADDRESS + 0x10, 8]);
+		assert_eq!(3, call(&mut cpu, SYS_READ, PARAMETER_ADDRESS));
+		assert_eq!(b"hello".to_vec(), load_bytes(&mut cpu, BUFFER_ADDRESS + 0x10, 5));
+		assert_eq!(8, call(&mut cpu, SYS_READ, PARAMETER_ADDRESS));
+
+		// Files outside of the root directory can't be opened
+		assert_eq!(-1, open(&mut cpu, "../test.txt", 0));
+		assert_eq!(-1, open(&mut cpu, "/etc/passwd", 0));
+		assert_eq!(-1, open(&mut cpu, "not_exist.txt", 0));
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn symbolic_link() {
+		let root = create_root("semihosting_symbolic_link");
+		let outside = create_root("semihosting_symbolic_link_outside");
+		match fs::write(outside.join("secret.txt"), b"secret") {
+			Ok(()) => {},
+			Err(_e) => panic!("Failed to write")
+		};
+		for &(target, link) in [
+			(&outside, "link"),
+			(&outside.join("secret.txt"), "secret_link.txt"),
+			(&outside.join("created.txt"), "dangling_link.txt")
+		].iter() {
+			match std::os::unix::fs::symlink(target, root.join(link)) {
+				Ok(()) => {},
+				Err(_e) => panic!("Failed to create symbolic link")
+			};
+		}
+		match fs::write(root.join("inside.txt"), b"inside") {
+			Ok(()) => {},
+			Err(_e) => panic!("Failed to write")
+		};
+		match std::os::unix::fs::symlink(root.join("inside.txt"), root.join("inside_link.txt")) {
+			Ok(()) => {},
+			Err(_e) => panic!("Failed to create symbolic link")
+		};
+		let mut cpu = create_cpu(root.clone());
+
+		// Links pointing outside of the root directory can't be followed
+		assert_eq!(-1, open(&mut cpu, "link/secret.txt", 0));
+		assert_eq!(-1, open(&mut cpu, "secret_link.txt", 0));
+		assert_eq!(-1, open(&mut cpu, "link/created.txt", 4));
+		assert_eq!(-1, open(&mut cpu, "dangling_link.txt", 4));
+		assert!(!outside.join("created.txt").exists());
+
+		// Links inside of the root directory can be followed
+		assert!(open(&mut cpu, "inside_link.txt", 0) > 0);
+
+		let _ = fs::remove_dir_all(&root);
+		let _ = fs::remove_dir_all(&outside);
+	}
+
+	#[test]
+	fn command_line_time_and_exit() {
+		let mut cpu = create_cpu(std::env::temp_dir());
+		store_parameters(&mut cpu, &[BUFFER_ADDRESS, 0x20]);
+		assert_eq!(0, call(&mut cpu, SYS_GET_CMDLINE, PARAMETER_ADDRESS));
+		assert_eq!(b"program arg\0".to_vec(), load_bytes(&mut cpu, BUFFER_ADDRESS, 12));
+		match cpu.get_mut_mmu().load_doubleword(PARAMETER_ADDRESS + 8) {
+			Ok(length) => assert_eq!(11, length),
+			Err(_e) => panic!("Failed to load")
+		};
+		// Buffer too small
+		store_parameters(&mut cpu, &[BUFFER_ADDRESS, 11]);
+		assert_eq!(-1, call(&mut cpu, SYS_GET_CMDLINE, PARAMETER_ADDRESS));
+
+		assert!(call(&mut cpu, SYS_CLOCK, 0) >= 0);
+		assert!(call(&mut cpu, SYS_TIME, 0) > 0);
+		// Unknown operation
+		assert_eq!(-1, call(&mut cpu, 0x100, 0));
+
+		assert_eq!(None, cpu.get_exit_code());
+		store_parameters(&mut cpu, &[ADP_STOPPED_APPLICATION_EXIT, 3]);
+		call(&mut cpu, SYS_EXIT, PARAMETER_ADDRESS);
+		assert_eq!(Some(3), cpu.get_exit_code());
+
+		// 32-bit SYS_EXIT takes the reason as parameter
+		let mut cpu = create_cpu(std::env::temp_dir());
+		cpu.update_xlen(Xlen::Bit32);
+		call(&mut cpu, SYS_EXIT, 0x20023); // ADP_Stopped_RunTimeErrorUnknown
+		assert_eq!(Some(1), cpu.get_exit_code());
+	}
+
+	#[test]
+	fn not_semihosting_call() {
+		let mut cpu = create_cpu(std::env::temp_dir());
+		// EBREAK without the surrounding instructions is Breakpoint trap
+		match cpu.get_mut_mmu().store_word(DRAM_BASE + 4, 0x00100073) {
+			Ok(()) => {},
+			Err(_e) => panic!("Failed to store")
+		};
+		cpu.update_pc(DRAM_BASE + 4);
+		cpu.write_register(10, SYS_EXIT as i64);
+		cpu.tick();
+		assert_eq!(0, cpu.read_pc()); // mtvec
+		assert_eq!(None, cpu.get_exit_code());
+
+		// The neighbors of EBREAK at the edges of main memory aren't mapped
+		for &address in [DRAM_BASE, DRAM_BASE + 0xffc].iter() {
+			match cpu.get_mut_mmu().store_word(address, 0x00100073) {
+				Ok(()) => {},
+				Err(_e) => panic!("Failed to store")
+			};
+			cpu.update_pc(address);
+			cpu.tick();
+			assert_eq!(0, cpu.read_pc());
+			assert_eq!(None, cpu.get_exit_code());
+		}
+	}
+}
@@ -0,0 +1,2032 @@
+use cpu::Xlen;
+use fpu;
+use fpu::{Format, RoundingMode, DOUBLE, SINGLE};
+
+/// Default vector register length in bits
+pub const DEFAULT_VLEN: usize = 128;
+
+/// Maximum element width in bits
+const ELEN: usize = 64;
+
+// Fixed-point rounding modes in vxrm
+const VXRM_RNU: u8 = 0;
+const VXRM_RNE: u8 = 1;
+const VXRM_RDN: u8 = 2;
+
+/// The last source operand of vector arithmetic instruction. `vs1` register
+/// or scalar value from `rs1` or immediate.
+#[derive(Clone)]
+pub enum Operand {
+	Vector(usize),
+	Scalar(u64)
+}
+
+/// Error of vector operation whose operands are invalid for the current
+/// `vtype` or overlap illegally. `Cpu` raises IllegalInstruction trap for it.
+#[derive(Debug, PartialEq)]
+pub struct IllegalOperation;
+
+/// Decoded operands of vector arithmetic instruction
+pub struct VectorInstruction {
+	pub vd: usize,
+	pub vs2: usize,
+	pub operand: Operand,
+	/// Whether the operation is masked by `v0`, `vm` bit is zero
+	pub masked: bool
+}
+
+impl VectorInstruction {
+	/// Returns the instruction whose immediate operand is zero-extended
+	/// instead of sign-extended. Shifts, slides, and gathers take unsigned
+	/// 5-bit immediate.
+	pub fn with_unsigned_immediate(&self) -> VectorInstruction {
+		VectorInstruction {
+			vd: self.vd,
+			vs2: self.vs2,
+			operand: match self.operand {
+				Operand::Vector(register) => Operand::Vector(register),
+				Operand::Scalar(value) => Operand::Scalar(value & 0x1f)
+			},
+			masked: self.masked
+		}
+	}
+}
+
+/// State element operations refer to and update
+pub struct ElementContext {
+	/// Selected element width in bits
+	pub sew: usize,
+	/// Fixed-point rounding mode, `vxrm`
+	pub vxrm: u8,
+	/// Set when fixed-point operation saturates
+	pub saturated: bool,
+	pub rounding_mode: RoundingMode,
+	/// Accrued floating-point exception flags
+	pub flags: u8
+}
+
+/// Element operation taking the destination element, the `vs2` element,
+/// and the `vs1` element or scalar. Elements are raw bits zero-extended
+/// to 64-bit. The destination element is used only by multiply-add.
+pub type ElementOperation = fn(&mut ElementContext, u64, u64, u64) -> u64;
+
+/// Element comparison taking the `vs2` element and the `vs1` element or scalar
+pub type ElementComparison = fn(&mut ElementContext, u64, u64) -> bool;
+
+/// Element operation taking the `vs2` element, the `vs1` element or scalar,
+/// and the carry in `v0`
+pub type CarryOperation = fn(&mut ElementContext, u64, u64, bool) -> u64;
+
+/// Element comparison taking the `vs2` element, the `vs1` element or scalar,
+/// and the carry in `v0`, and returning carry out
+pub type CarryOutOperation = fn(&mut ElementContext, u64, u64, bool) -> bool;
+
+/// Unary element operation taking the `vs2` element
+pub type ElementConversion = fn(&mut ElementContext, u64) -> u64;
+
+/// Source and destination element widths of unary operations
+pub enum ConversionKind {
+	/// Both are SEW
+	Single,
+	/// The destination is 2 * SEW
+	Widening,
+	/// The source is 2 * SEW
+	Narrowing
+}
+
+/// Emulates vector unit of the RISC-V Vector extension 1.0. Holds the vector
+/// registers, `vtype`, `vl`, `vstart`, `vxrm`, and `vxsat` and executes
+/// vector arithmetic. Memory accesses are done by [`Cpu`](../cpu/struct.Cpu.html).
+///
+/// Tail and inactive elements are always left undisturbed, which is one of
+/// the behaviors agnostic policies allow.
+pub struct Vpu {
+	/// Vector register length in bits
+	vlen: usize,
+
+	/// 32 vector registers. Elements are stored in little endian and
+	/// a register group is consecutive registers.
+	registers: Vec<u8>,
+
+	/// `vtype` CSR except for `vill` bit
+	vtype: u64,
+	vill: bool,
+	vl: u64,
+	vstart: u64,
+	vxrm: u8,
+	vxsat: bool
+}
+
+/// Copy of vector registers source operands are read from, so that
+/// a destination overlapping sources doesn't affect the result
+struct Snapshot {
+	registers: Vec<u8>,
+	vlenb: usize
+}
+
+impl Snapshot {
+	fn read(&self, register: usize, index: usize, width: usize) -> u64 {
+		read_bytes(&self.registers, register * self.vlenb + index * width / 8, width)
+	}
+
+	fn is_mask_set(&self, register: usize, index: usize) -> bool {
+		((self.registers[register * self.vlenb + index / 8] >> (index % 8)) & 1) == 1
+	}
+
+	fn is_active(&self, masked: bool, index: usize) -> bool {
+		!masked || self.is_mask_set(0, index)
+	}
+
+	fn read_operand(&self, operand: &Operand, index: usize, width: usize) -> u64 {
+		match operand {
+			Operand::Vector(register) => self.read(*register, index, width),
+			Operand::Scalar(value) => truncate(*value, width)
+		}
+	}
+}
+
+fn read_bytes(bytes: &[u8], offset: usize, width: usize) -> u64 {
+	let mut value = 0;
+	for i in 0..(width / 8) {
+		value |= (bytes[offset + i] as u64) << (i * 8);
+	}
+	value
+}
+
+fn write_bytes(bytes: &mut [u8], offset: usize, width: usize, value: u64) {
+	for i in 0..(width / 8) {
+		bytes[offset + i] = (value >> (i * 8)) as u8;
+	}
+}
+
+/// Returns whether `register` is a valid register group number for
+/// EMUL 2^`emul_log2`.
+///
+/// # Arguments
+/// * `register`
+/// * `emul_log2`
+fn is_aligned(register: usize, emul_log2: i32) -> bool {
+	match emul_log2 > 0 {
+		true => (register & ((1 << emul_log2) - 1)) == 0,
+		false => true
+	}
+}
+
+/// Returns whether two register groups overlap.
+///
+/// # Arguments
+/// * `a` The first register of a group
+/// * `a_emul_log2`
+/// * `b` The first register of the other group
+/// * `b_emul_log2`
+fn overlaps(a: usize, a_emul_log2: i32, b: usize, b_emul_log2: i32) -> bool {
+	let a_end = a + (1 << a_emul_log2.max(0));
+	let b_end = b + (1 << b_emul_log2.max(0));
+	a < b_end && b < a_end
+}
+
+impl Vpu {
+	/// Creates a new `Vpu`. `vtype.vill` is set as recommended at reset.
+	///
+	/// # Arguments
+	/// * `vlen` Vector register length in bits. Must be a power of two from 64 to 65536.
+	pub fn new(vlen: usize) -> Self {
+		assert!(vlen.is_power_of_two() && (ELEN..=65536).contains(&vlen),
+			"VLEN must be a power of two from 64 to 65536. {}", vlen);
+		Vpu {
+			vlen,
+			registers: vec![0; vlen / 8 * 32],
+			vtype: 0,
+			vill: true,
+			vl: 0,
+			vstart: 0,
+			vxrm: 0,
+			vxsat: false
+		}
+	}
+
+	/// Returns vector register length in bytes, the value of `vlenb` CSR
+	pub fn get_vlenb(&self) -> u64 {
+		(self.vlen / 8) as u64
+	}
+
+	/// Returns `vtype` CSR value
+	///
+	/// # Arguments
+	/// * `xlen` `vill` bit is the most significant bit of XLEN
+	pub fn read_vtype(&self, xlen: &Xlen) -> u64 {
+		match self.vill {
+			true => match xlen {
+				Xlen::Bit32 => 1 << 31,
+				Xlen::Bit64 => 1 << 63
+			},
+			false => self.vtype
+		}
+	}
+
+	pub fn is_vill(&self) -> bool {
+		self.vill
+	}
+
+	pub fn get_vl(&self) -> u64 {
+		self.vl
+	}
+
+	/// Reduces `vl`. Used by fault-only-first loads.
+	///
+	/// # Arguments
+	/// * `vl`
+	pub fn truncate_vl(&mut self, vl: u64) {
+		self.vl = self.vl.min(vl);
+	}
+
+	pub fn get_vstart(&self) -> u64 {
+		self.vstart
+	}
+
+	/// Writes `vstart` CSR. Only the bits needed to hold the maximum
+	/// element index are writable.
+	///
+	/// # Arguments
+	/// * `value`
+	pub fn write_vstart(&mut self, value: u64) {
+		self.vstart = value & (self.vlen as u64 - 1);
+	}
+
+	pub fn read_vxrm(&self) -> u64 {
+		self.vxrm as u64
+	}
+
+	pub fn write_vxrm(&mut self, value: u64) {
+		self.vxrm = (value & 0x3) as u8;
+	}
+
+	pub fn read_vxsat(&self) -> u64 {
+		self.vxsat as u64
+	}
+
+	pub fn write_vxsat(&mut self, value: u64) {
+		self.vxsat = (value & 1) == 1;
+	}
+
+	/// Returns selected element width in bits
+	pub fn get_sew(&self) -> usize {
+		8 << ((self.vtype >> 3) & 0x7)
+	}
+
+	/// Returns log2 of vector register group multiplier, -3 to 3
+	pub fn get_lmul_log2(&self) -> i32 {
+		let lmul = (self.vtype & 0x7) as i32;
+		match lmul >= 4 {
+			true => lmul - 8,
+			false => lmul
+		}
+	}
+
+	/// Returns the maximum number of elements with the current `vtype`
+	pub fn get_vlmax(&self) -> u64 {
+		let elements = (self.vlen / self.get_sew()) as u64;
+		let lmul_log2 = self.get_lmul_log2();
+		match lmul_log2 >= 0 {
+			true => elements << lmul_log2,
+			false => elements >> -lmul_log2
+		}
+	}
+
+	/// Updates `vtype` and `vl` as `vsetvl` instructions do and returns
+	/// the new `vl`. Unsupported `vtype` sets `vill` and `vl` to zero.
+	///
+	/// # Arguments
+	/// * `vtype` Requested `vtype`
+	/// * `avl` Application vector length. `None` keeps the current `vl`.
+	pub fn set_vtype(&mut self, vtype: u64, avl: Option<u64>) -> u64 {
+		self.vstart = 0;
+		let sew = 8 << ((vtype >> 3) & 0x7);
+		let lmul_encoding = vtype & 0x7;
+		let lmul_log2 = match lmul_encoding >= 4 {
+			true => lmul_encoding as i32 - 8,
+			false => lmul_encoding as i32
+		};
+		// SEW must not exceed ELEN * LMUL
+		let valid = (vtype >> 8) == 0 && lmul_encoding != 4 && sew <= ELEN &&
+			(lmul_log2 >= 0 || sew <= (ELEN >> -lmul_log2));
+		if !valid {
+			self.vill = true;
+			self.vtype = 0;
+			self.vl = 0;
+			return 0;
+		}
+		self.vill = false;
+		self.vtype = vtype;
+		let vlmax = self.get_vlmax();
+		self.vl = match avl {
+			Some(avl) => avl.min(vlmax),
+			None => self.vl.min(vlmax)
+		};
+		self.vl
+	}
+
+	/// Reads an element of a vector register group.
+	///
+	/// # Arguments
+	/// * `register` The first register of the group
+	/// * `index` Element index
+	/// * `width` Element width in bits
+	pub fn read_element(&self, register: usize, index: usize, width: usize) -> u64 {
+		read_bytes(&self.registers, register * self.vlen / 8 + index * width / 8, width)
+	}
+
+	/// Writes an element of a vector register group.
+	///
+	/// # Arguments
+	/// * `register` The first register of the group
+	/// * `index` Element index
+	/// * `width` Element width in bits
+	/// * `value`
+	pub fn write_element(&mut self, register: usize, index: usize, width: usize, value: u64) {
+		let offset = register * self.vlen / 8 + index * width / 8;
+		write_bytes(&mut self.registers, offset, width, value);
+	}
+
+	fn write_mask(&mut self, register: usize, index: usize, value: bool) {
+		let offset = register * self.vlen / 8 + index / 8;
+		match value {
+			true => self.registers[offset] |= 1 << (index % 8),
+			false => self.registers[offset] &= !(1 << (index % 8))
+		};
+	}
+
+	/// Returns whether the element is active. Elements are active if
+	/// unmasked, or if the corresponding bit of `v0` is set.
+	///
+	/// # Arguments
+	/// * `masked`
+	/// * `index` Element index
+	pub fn is_active(&self, masked: bool, index: usize) -> bool {
+		!masked || ((self.registers[index / 8] >> (index % 8)) & 1) == 1
+	}
+
+	fn snapshot(&self) -> Snapshot {
+		Snapshot {
+			registers: self.registers.clone(),
+			vlenb: self.vlen / 8
+		}
+	}
+
+	/// Returns the range of element indices instructions process
+	fn get_body(&self) -> std::ops::Range<usize> {
+		(self.vstart as usize)..(self.vl as usize)
+	}
+
+	/// Checks that register groups are aligned to their EMUL and that
+	/// masked instruction doesn't overwrite the mask.
+	///
+	/// # Arguments
+	/// * `instruction`
+	/// * `vd_emul_log2`
+	/// * `vs2_emul_log2`
+	/// * `vs1_emul_log2`
+	fn check_operands(&self, instruction: &VectorInstruction, vd_emul_log2: i32,
+		vs2_emul_log2: i32, vs1_emul_log2: i32) -> Result<(), IllegalOperation> {
+		if vd_emul_log2 > 3 || vs2_emul_log2 > 3 || vs1_emul_log2 > 3 ||
+			vd_emul_log2 < -3 || vs2_emul_log2 < -3 || vs1_emul_log2 < -3 {
+			return Err(IllegalOperation);
+		}
+		if instruction.masked && instruction.vd == 0 {
+			return Err(IllegalOperation);
+		}
+		let vs1_aligned = match instruction.operand {
+			Operand::Vector(vs1) => is_aligned(vs1, vs1_emul_log2),
+			Operand::Scalar(_) => true
+		};
+		match is_aligned(instruction.vd, vd_emul_log2) && is_aligned(instruction.vs2, vs2_emul_log2) && vs1_aligned {
+			true => Ok(()),
+			false => Err(IllegalOperation)
+		}
+	}
+
+	/// Executes single-width element operation for each active element,
+	/// vd[i] = op(vd[i], vs2[i], vs1[i] or scalar).
+	///
+	/// # Arguments
+	/// * `context`
+	/// * `instruction`
+	/// * `operation`
+	pub fn binary(&mut self, context: &mut ElementContext, instruction: &VectorInstruction,
+		operation: ElementOperation) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		match self.check_operands(instruction, lmul_log2, lmul_log2, lmul_log2) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		let source = self.snapshot();
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let a = source.read(instruction.vs2, i, sew);
+			let b = source.read_operand(&instruction.operand, i, sew);
+			let d = source.read(instruction.vd, i, sew);
+			let result = operation(context, d, a, b);
+			self.write_element(instruction.vd, i, sew, truncate(result, sew));
+		}
+		Ok(())
+	}
+
+	/// Executes widening element operation whose destination is 2 * SEW.
+	///
+	/// # Arguments
+	/// * `context`
+	/// * `instruction`
+	/// * `wide_source` Whether `vs2` is 2 * SEW, too
+	/// * `operation`
+	pub fn widening(&mut self, context: &mut ElementContext, instruction: &VectorInstruction,
+		wide_source: bool, operation: ElementOperation) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		let vs2_width = match wide_source {
+			true => sew * 2,
+			false => sew
+		};
+		let vs2_emul_log2 = match wide_source {
+			true => lmul_log2 + 1,
+			false => lmul_log2
+		};
+		if sew * 2 > ELEN {
+			return Err(IllegalOperation);
+		}
+		match self.check_operands(instruction, lmul_log2 + 1, vs2_emul_log2, lmul_log2) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		let source = self.snapshot();
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let a = source.read(instruction.vs2, i, vs2_width);
+			let b = source.read_operand(&instruction.operand, i, sew);
+			let d = source.read(instruction.vd, i, sew * 2);
+			let result = operation(context, d, a, b);
+			self.write_element(instruction.vd, i, sew * 2, truncate(result, sew * 2));
+		}
+		Ok(())
+	}
+
+	/// Executes narrowing element operation whose `vs2` is 2 * SEW.
+	///
+	/// # Arguments
+	/// * `context`
+	/// * `instruction`
+	/// * `operation`
+	pub fn narrowing(&mut self, context: &mut ElementContext, instruction: &VectorInstruction,
+		operation: ElementOperation) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		if sew * 2 > ELEN {
+			return Err(IllegalOperation);
+		}
+		match self.check_operands(instruction, lmul_log2, lmul_log2 + 1, lmul_log2) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		let source = self.snapshot();
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let a = source.read(instruction.vs2, i, sew * 2);
+			let b = source.read_operand(&instruction.operand, i, sew);
+			let d = source.read(instruction.vd, i, sew);
+			let result = operation(context, d, a, b);
+			self.write_element(instruction.vd, i, sew, truncate(result, sew));
+		}
+		Ok(())
+	}
+
+	/// Executes comparison for each active element and writes the results
+	/// to mask register `vd`.
+	///
+	/// # Arguments
+	/// * `context`
+	/// * `instruction`
+	/// * `comparison`
+	pub fn compare(&mut self, context: &mut ElementContext, instruction: &VectorInstruction,
+		comparison: ElementComparison) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		// Mask destination can overlap v0
+		let unmasked = VectorInstruction {
+			vd: instruction.vd,
+			vs2: instruction.vs2,
+			operand: instruction.operand.clone(),
+			masked: false
+		};
+		match self.check_operands(&unmasked, 0, lmul_log2, lmul_log2) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		let source = self.snapshot();
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let a = source.read(instruction.vs2, i, sew);
+			let b = source.read_operand(&instruction.operand, i, sew);
+			let result = comparison(context, a, b);
+			self.write_mask(instruction.vd, i, result);
+		}
+		Ok(())
+	}
+
+	/// Executes operation taking `v0` as carry for all the body elements.
+	/// Used by `VADC`, `VSBC`, and `VMERGE` which must be encoded as masked.
+	///
+	/// # Arguments
+	/// * `context`
+	/// * `instruction`
+	/// * `operation`
+	pub fn with_carry(&mut self, context: &mut ElementContext, instruction: &VectorInstruction,
+		operation: CarryOperation) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		if !instruction.masked {
+			return Err(IllegalOperation);
+		}
+		match self.check_operands(instruction, lmul_log2, lmul_log2, lmul_log2) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		let source = self.snapshot();
+		for i in self.get_body() {
+			let a = source.read(instruction.vs2, i, sew);
+			let b = source.read_operand(&instruction.operand, i, sew);
+			let result = operation(context, a, b, source.is_mask_set(0, i));
+			self.write_element(instruction.vd, i, sew, truncate(result, sew));
+		}
+		Ok(())
+	}
+
+	/// Executes operation producing carry out to mask register `vd` for
+	/// all the body elements. Carry in is `v0` if masked, otherwise zero.
+	///
+	/// # Arguments
+	/// * `context`
+	/// * `instruction`
+	/// * `operation`
+	pub fn carry_out(&mut self, context: &mut ElementContext, instruction: &VectorInstruction,
+		operation: CarryOutOperation) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		if instruction.masked && instruction.vd == 0 {
+			return Err(IllegalOperation);
+		}
+		let vs1_aligned = match instruction.operand {
+			Operand::Vector(vs1) => is_aligned(vs1, lmul_log2),
+			Operand::Scalar(_) => true
+		};
+		if !is_aligned(instruction.vs2, lmul_log2) || !vs1_aligned {
+			return Err(IllegalOperation);
+		}
+		let source = self.snapshot();
+		for i in self.get_body() {
+			let a = source.read(instruction.vs2, i, sew);
+			let b = source.read_operand(&instruction.operand, i, sew);
+			let carry = instruction.masked && source.is_mask_set(0, i);
+			let result = operation(context, a, b, carry);
+			self.write_mask(instruction.vd, i, result);
+		}
+		Ok(())
+	}
+
+	/// Executes reduction. The initial value is `vs1[0]` and the result
+	/// is written to `vd[0]`. The element operation takes the accumulator
+	/// as `vs2` element and an active `vs2` element as `vs1` element.
+	///
+	/// # Arguments
+	/// * `context`
+	/// * `instruction`
+	/// * `widening` Whether the accumulator is 2 * SEW
+	/// * `operation`
+	pub fn reduction(&mut self, context: &mut ElementContext, instruction: &VectorInstruction,
+		widening: bool, operation: ElementOperation) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		let accumulator_width = match widening {
+			true => sew * 2,
+			false => sew
+		};
+		if accumulator_width > ELEN || self.vstart != 0 || !is_aligned(instruction.vs2, lmul_log2) {
+			return Err(IllegalOperation);
+		}
+		if self.vl == 0 {
+			return Ok(());
+		}
+		let source = self.snapshot();
+		let mut accumulator = source.read_operand(&instruction.operand, 0, accumulator_width);
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let element = source.read(instruction.vs2, i, sew);
+			accumulator = truncate(operation(context, 0, accumulator, element), accumulator_width);
+		}
+		self.write_element(instruction.vd, 0, accumulator_width, accumulator);
+		Ok(())
+	}
+
+	/// Executes logical operation of mask registers for all the body elements.
+	///
+	/// # Arguments
+	/// * `instruction`
+	/// * `operation` Takes `vs2` and `vs1` mask bits
+	pub fn mask_logical(&mut self, instruction: &VectorInstruction, operation: fn(bool, bool) -> bool) -> Result<(), IllegalOperation> {
+		let vs1 = match instruction.operand {
+			Operand::Vector(vs1) => vs1,
+			Operand::Scalar(_) => return Err(IllegalOperation)
+		};
+		if instruction.masked {
+			return Err(IllegalOperation);
+		}
+		let source = self.snapshot();
+		for i in self.get_body() {
+			let result = operation(source.is_mask_set(instruction.vs2, i), source.is_mask_set(vs1, i));
+			self.write_mask(instruction.vd, i, result);
+		}
+		Ok(())
+	}
+
+	/// Executes unary element operation, like conversion, whose source and
+	/// destination widths may differ.
+	///
+	/// # Arguments
+	/// * `context`
+	/// * `instruction`
+	/// * `kind` Source and destination widths
+	/// * `floats` Whether the source and the destination are floating-point
+	///   values which must be single or double precision
+	/// * `operation`
+	pub fn convert(&mut self, context: &mut ElementContext, instruction: &VectorInstruction,
+		kind: ConversionKind, floats: (bool, bool), operation: ElementConversion) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		let (from_width, to_width, from_emul_log2, to_emul_log2) = match kind {
+			ConversionKind::Single => (sew, sew, lmul_log2, lmul_log2),
+			ConversionKind::Widening => (sew, sew * 2, lmul_log2, lmul_log2 + 1),
+			ConversionKind::Narrowing => (sew * 2, sew, lmul_log2 + 1, lmul_log2)
+		};
+		let is_float_width = |width: usize| width == 32 || width == 64;
+		if from_width > ELEN || to_width > ELEN ||
+			(floats.0 && !is_float_width(from_width)) || (floats.1 && !is_float_width(to_width)) {
+			return Err(IllegalOperation);
+		}
+		match self.check_operands(instruction, to_emul_log2, from_emul_log2, 0) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		let source = self.snapshot();
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let a = source.read(instruction.vs2, i, from_width);
+			let result = operation(context, a);
+			self.write_element(instruction.vd, i, to_width, truncate(result, to_width));
+		}
+		Ok(())
+	}
+
+	/// Executes `VZEXT` and `VSEXT` whose source is SEW / 2^`factor_log2`.
+	///
+	/// # Arguments
+	/// * `instruction`
+	/// * `factor_log2`
+	/// * `signed`
+	pub fn extend(&mut self, instruction: &VectorInstruction, factor_log2: i32, signed: bool) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		let from_width = sew >> factor_log2;
+		if from_width < 8 {
+			return Err(IllegalOperation);
+		}
+		match self.check_operands(instruction, lmul_log2, lmul_log2 - factor_log2, 0) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		let source = self.snapshot();
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let value = source.read(instruction.vs2, i, from_width);
+			let result = match signed {
+				true => sign_extend(value, from_width) as u64,
+				false => value
+			};
+			self.write_element(instruction.vd, i, sew, truncate(result, sew));
+		}
+		Ok(())
+	}
+
+	/// Executes `VSLIDEUP`, vd[i + offset] = vs2[i]. The offset is
+	/// the scalar operand.
+	///
+	/// # Arguments
+	/// * `instruction`
+	pub fn slide_up(&mut self, instruction: &VectorInstruction) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		let offset = match instruction.operand {
+			Operand::Scalar(value) => value,
+			Operand::Vector(_) => return Err(IllegalOperation)
+		};
+		match self.check_operands(instruction, lmul_log2, lmul_log2, 0) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		if overlaps(instruction.vd, lmul_log2, instruction.vs2, lmul_log2) {
+			return Err(IllegalOperation);
+		}
+		let source = self.snapshot();
+		let start = self.vstart.max(offset) as usize;
+		for i in start..(self.vl as usize) {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let value = source.read(instruction.vs2, i - offset as usize, sew);
+			self.write_element(instruction.vd, i, sew, value);
+		}
+		Ok(())
+	}
+
+	/// Executes `VSLIDEDOWN`, vd[i] = vs2[i + offset]. The offset is
+	/// the scalar operand. Elements beyond VLMAX are read as zero.
+	///
+	/// # Arguments
+	/// * `instruction`
+	pub fn slide_down(&mut self, instruction: &VectorInstruction) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		let offset = match instruction.operand {
+			Operand::Scalar(value) => value,
+			Operand::Vector(_) => return Err(IllegalOperation)
+		};
+		match self.check_operands(instruction, lmul_log2, lmul_log2, 0) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		let vlmax = self.get_vlmax();
+		let source = self.snapshot();
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let value = match (i as u64).checked_add(offset) {
+				Some(index) if index < vlmax => source.read(instruction.vs2, index as usize, sew),
+				_ => 0
+			};
+			self.write_element(instruction.vd, i, sew, value);
+		}
+		Ok(())
+	}
+
+	/// Executes `VSLIDE1UP`, vd[0] = scalar and vd[i + 1] = vs2[i].
+	///
+	/// # Arguments
+	/// * `instruction`
+	pub fn slide1_up(&mut self, instruction: &VectorInstruction) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		match self.check_operands(instruction, lmul_log2, lmul_log2, 0) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		if overlaps(instruction.vd, lmul_log2, instruction.vs2, lmul_log2) {
+			return Err(IllegalOperation);
+		}
+		let source = self.snapshot();
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let value = match i {
+				0 => source.read_operand(&instruction.operand, 0, sew),
+				_ => source.read(instruction.vs2, i - 1, sew)
+			};
+			self.write_element(instruction.vd, i, sew, value);
+		}
+		Ok(())
+	}
+
+	/// Executes `VSLIDE1DOWN`, vd[i] = vs2[i + 1] and vd[vl - 1] = scalar.
+	///
+	/// # Arguments
+	/// * `instruction`
+	pub fn slide1_down(&mut self, instruction: &VectorInstruction) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		match self.check_operands(instruction, lmul_log2, lmul_log2, 0) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		let last = self.vl as usize - 1;
+		let source = self.snapshot();
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let value = match i == last {
+				true => source.read_operand(&instruction.operand, i, sew),
+				false => source.read(instruction.vs2, i + 1, sew)
+			};
+			self.write_element(instruction.vd, i, sew, value);
+		}
+		Ok(())
+	}
+
+	/// Executes `VRGATHER`, vd[i] = vs2[index[i]]. Indices are from `vs1`
+	/// or scalar. Elements beyond VLMAX are read as zero.
+	///
+	/// # Arguments
+	/// * `instruction`
+	/// * `index_width` Width of `vs1` elements. SEW, or 16 for `VRGATHEREI16`.
+	pub fn gather(&mut self, instruction: &VectorInstruction, index_width: usize) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		let index_emul_log2 = lmul_log2 + index_width.trailing_zeros() as i32 - sew.trailing_zeros() as i32;
+		match self.check_operands(instruction, lmul_log2, lmul_log2, index_emul_log2) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		let vs1_overlaps = match instruction.operand {
+			Operand::Vector(vs1) => overlaps(instruction.vd, lmul_log2, vs1, index_emul_log2),
+			Operand::Scalar(_) => false
+		};
+		if vs1_overlaps || overlaps(instruction.vd, lmul_log2, instruction.vs2, lmul_log2) {
+			return Err(IllegalOperation);
+		}
+		let vlmax = self.get_vlmax();
+		let source = self.snapshot();
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let index = match instruction.operand {
+				Operand::Vector(vs1) => source.read(vs1, i, index_width),
+				Operand::Scalar(value) => value
+			};
+			let value = match index < vlmax {
+				true => source.read(instruction.vs2, index as usize, sew),
+				false => 0
+			};
+			self.write_element(instruction.vd, i, sew, value);
+		}
+		Ok(())
+	}
+
+	/// Executes `VCOMPRESS`. Packs the `vs2` elements whose mask bit in
+	/// `vs1` is set into `vd`.
+	///
+	/// # Arguments
+	/// * `instruction`
+	pub fn compress(&mut self, instruction: &VectorInstruction) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		let vs1 = match instruction.operand {
+			Operand::Vector(vs1) => vs1,
+			Operand::Scalar(_) => return Err(IllegalOperation)
+		};
+		if instruction.masked || self.vstart != 0 {
+			return Err(IllegalOperation);
+		}
+		match self.check_operands(instruction, lmul_log2, lmul_log2, 0) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		if overlaps(instruction.vd, lmul_log2, instruction.vs2, lmul_log2) ||
+			overlaps(instruction.vd, lmul_log2, vs1, 0) {
+			return Err(IllegalOperation);
+		}
+		let source = self.snapshot();
+		let mut count = 0;
+		for i in self.get_body() {
+			if source.is_mask_set(vs1, i) {
+				let value = source.read(instruction.vs2, i, sew);
+				self.write_element(instruction.vd, count, sew, value);
+				count += 1;
+			}
+		}
+		Ok(())
+	}
+
+	/// Executes whole register move `VMV<nr>R.V` which doesn't depend on
+	/// `vtype` and `vl`.
+	///
+	/// # Arguments
+	/// * `instruction`
+	/// * `count` The number of registers, 1, 2, 4, or 8
+	pub fn move_registers(&mut self, instruction: &VectorInstruction, count: usize) -> Result<(), IllegalOperation> {
+		if (instruction.vd & (count - 1)) != 0 || (instruction.vs2 & (count - 1)) != 0 {
+			return Err(IllegalOperation);
+		}
+		let vlenb = self.vlen / 8;
+		// vstart is counted in SEW elements
+		let start = match self.vill {
+			true => 0,
+			false => self.vstart as usize * self.get_sew() / 8
+		};
+		for i in start..(count * vlenb) {
+			self.registers[instruction.vd * vlenb + i] = self.registers[instruction.vs2 * vlenb + i];
+		}
+		Ok(())
+	}
+
+	/// Returns `vs2[0]` sign-extended from SEW, used by `VMV.X.S` and `VFMV.F.S`.
+	///
+	/// # Arguments
+	/// * `instruction`
+	pub fn move_to_scalar(&self, instruction: &VectorInstruction) -> Result<u64, IllegalOperation> {
+		let sew = self.get_sew();
+		Ok(sign_extend(self.read_element(instruction.vs2, 0, sew), sew) as u64)
+	}
+
+	/// Writes scalar to `vd[0]`, used by `VMV.S.X` and `VFMV.S.F`. Nothing
+	/// is written if `vstart` >= `vl`.
+	///
+	/// # Arguments
+	/// * `instruction`
+	pub fn move_from_scalar(&mut self, instruction: &VectorInstruction) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let value = match instruction.operand {
+			Operand::Scalar(value) => value,
+			Operand::Vector(_) => return Err(IllegalOperation)
+		};
+		if self.vstart < self.vl {
+			self.write_element(instruction.vd, 0, sew, truncate(value, sew));
+		}
+		Ok(())
+	}
+
+	/// Returns the number of the active set bits of mask register `vs2`,
+	/// used by `VCPOP.M`.
+	///
+	/// # Arguments
+	/// * `instruction`
+	pub fn count_population(&self, instruction: &VectorInstruction) -> Result<u64, IllegalOperation> {
+		if self.vstart != 0 {
+			return Err(IllegalOperation);
+		}
+		let source = self.snapshot();
+		let mut count = 0;
+		for i in self.get_body() {
+			if source.is_active(instruction.masked, i) && source.is_mask_set(instruction.vs2, i) {
+				count += 1;
+			}
+		}
+		Ok(count)
+	}
+
+	/// Returns the index of the first active set bit of mask register `vs2`,
+	/// or -1 if none, used by `VFIRST.M`.
+	///
+	/// # Arguments
+	/// * `instruction`
+	pub fn find_first(&self, instruction: &VectorInstruction) -> Result<u64, IllegalOperation> {
+		if self.vstart != 0 {
+			return Err(IllegalOperation);
+		}
+		let source = self.snapshot();
+		for i in self.get_body() {
+			if source.is_active(instruction.masked, i) && source.is_mask_set(instruction.vs2, i) {
+				return Ok(i as u64);
+			}
+		}
+		Ok(-1i64 as u64)
+	}
+
+	/// Executes `VMSBF.M`, `VMSIF.M`, or `VMSOF.M`. Mask bits are written
+	/// depending on whether the first active set bit of `vs2` is before,
+	/// at, or after the element.
+	///
+	/// # Arguments
+	/// * `instruction`
+	/// * `operation` Takes whether the first set bit is found before the element
+	///   and whether the element is the first set bit
+	pub fn set_mask_by_first(&mut self, instruction: &VectorInstruction, operation: fn(bool, bool) -> bool) -> Result<(), IllegalOperation> {
+		if self.vstart != 0 || instruction.vd == instruction.vs2 || (instruction.masked && instruction.vd == 0) {
+			return Err(IllegalOperation);
+		}
+		let source = self.snapshot();
+		let mut found = false;
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			let is_first = !found && source.is_mask_set(instruction.vs2, i);
+			self.write_mask(instruction.vd, i, operation(found, is_first));
+			found = found || is_first;
+		}
+		Ok(())
+	}
+
+	/// Executes `VIOTA.M`, vd[i] is the number of the active set bits of
+	/// mask register `vs2` before the element.
+	///
+	/// # Arguments
+	/// * `instruction`
+	pub fn iota(&mut self, instruction: &VectorInstruction) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		if self.vstart != 0 {
+			return Err(IllegalOperation);
+		}
+		match self.check_operands(instruction, lmul_log2, 0, 0) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		if overlaps(instruction.vd, lmul_log2, instruction.vs2, 0) {
+			return Err(IllegalOperation);
+		}
+		let source = self.snapshot();
+		let mut count = 0;
+		for i in self.get_body() {
+			if !source.is_active(instruction.masked, i) {
+				continue;
+			}
+			self.write_element(instruction.vd, i, sew, truncate(count, sew));
+			if source.is_mask_set(instruction.vs2, i) {
+				count += 1;
+			}
+		}
+		Ok(())
+	}
+
+	/// Executes `VID.V`, vd[i] = i.
+	///
+	/// # Arguments
+	/// * `instruction`
+	pub fn id(&mut self, instruction: &VectorInstruction) -> Result<(), IllegalOperation> {
+		let sew = self.get_sew();
+		let lmul_log2 = self.get_lmul_log2();
+		match self.check_operands(instruction, lmul_log2, 0, 0) {
+			Ok(()) => {},
+			Err(e) => return Err(e)
+		};
+		for i in self.get_body() {
+			if self.is_active(instruction.masked, i) {
+				self.write_element(instruction.vd, i, sew, truncate(i as u64, sew));
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Returns `value` truncated to `width` bits
+fn truncate(value: u64, width: usize) -> u64 {
+	match width {
+		64 => value,
+		_ => value & ((1 << width) - 1)
+	}
+}
+
+/// Returns the lower `width` bits of `value` sign-extended
+fn sign_extend(value: u64, width: usize) -> i64 {
+	let shift = 64 - width;
+	((value << shift) as i64) >> shift
+}
+
+fn signed_min(width: usize) -> i64 {
+	sign_extend(1 << (width - 1), width)
+}
+
+fn signed_max(width: usize) -> i64 {
+	((1u64 << (width - 1)) - 1) as i64
+}
+
+fn unsigned_max(width: usize) -> u64 {
+	truncate(u64::MAX, width)
+}
+
+/// Shifts `value` right by `shift` bits and rounds it in fixed-point
+/// rounding mode `vxrm`.
+///
+/// # Arguments
+/// * `value`
+/// * `shift`
+/// * `vxrm`
+fn round_shift(value: i128, shift: u32, vxrm: u8) -> i128 {
+	if shift == 0 {
+		return value;
+	}
+	let lsb = (value >> shift) & 1;
+	let half = (value >> (shift - 1)) & 1;
+	let below_half = (value & ((1 << (shift - 1)) - 1)) != 0;
+	let increment = match vxrm {
+		VXRM_RNU => half,
+		VXRM_RNE => half & (below_half as i128 | lsb),
+		VXRM_RDN => 0,
+		// Round to odd, jamming
+		_ => (lsb == 0 && (value & ((1 << shift) - 1)) != 0) as i128
+	};
+	(value >> shift) + increment
+}
+
+/// Clamps `value` to signed `width` bits integer range and marks saturation
+fn clamp_signed(context: &mut ElementContext, value: i128, width: usize) -> u64 {
+	let min = signed_min(width) as i128;
+	let max = signed_max(width) as i128;
+	if value < min || value > max {
+		context.saturated = true;
+	}
+	value.max(min).min(max) as u64
+}
+
+/// Clamps `value` to unsigned `width` bits integer range and marks saturation
+fn clamp_unsigned(context: &mut ElementContext, value: i128, width: usize) -> u64 {
+	let max = unsigned_max(width) as i128;
+	if value < 0 || value > max {
+		context.saturated = true;
+	}
+	value.max(0).min(max) as u64
+}
+
+// Integer element operations. a is vs2 element, b is vs1 element or scalar,
+// d is vd element.
+
+pub fn add(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.wrapping_add(b)
+}
+
+pub fn sub(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.wrapping_sub(b)
+}
+
+pub fn rsub(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	b.wrapping_sub(a)
+}
+
+pub fn minu(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.min(b)
+}
+
+pub fn min(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	match sign_extend(a, context.sew) < sign_extend(b, context.sew) {
+		true => a,
+		false => b
+	}
+}
+
+pub fn maxu(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.max(b)
+}
+
+pub fn max(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	match sign_extend(a, context.sew) > sign_extend(b, context.sew) {
+		true => a,
+		false => b
+	}
+}
+
+pub fn and(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a & b
+}
+
+pub fn or(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a | b
+}
+
+pub fn xor(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a ^ b
+}
+
+pub fn sll(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a << (b & (context.sew as u64 - 1))
+}
+
+pub fn srl(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a >> (b & (context.sew as u64 - 1))
+}
+
+pub fn sra(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	(sign_extend(a, context.sew) >> (b & (context.sew as u64 - 1))) as u64
+}
+
+pub fn mul(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.wrapping_mul(b)
+}
+
+pub fn mulh(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let product = sign_extend(a, context.sew) as i128 * sign_extend(b, context.sew) as i128;
+	(product >> context.sew) as u64
+}
+
+pub fn mulhu(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	((a as u128 * b as u128) >> context.sew) as u64
+}
+
+pub fn mulhsu(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let product = sign_extend(a, context.sew) as i128 * b as i128;
+	(product >> context.sew) as u64
+}
+
+pub fn divu(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	match b {
+		0 => u64::MAX,
+		_ => a / b
+	}
+}
+
+pub fn div(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let a = sign_extend(a, context.sew);
+	let b = sign_extend(b, context.sew);
+	match (b, a == signed_min(context.sew) && b == -1) {
+		(0, _) => u64::MAX,
+		(_, true) => a as u64,
+		(_, false) => (a / b) as u64
+	}
+}
+
+pub fn remu(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	match b {
+		0 => a,
+		_ => a % b
+	}
+}
+
+pub fn rem(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let a = sign_extend(a, context.sew);
+	let b = sign_extend(b, context.sew);
+	match (b, a == signed_min(context.sew) && b == -1) {
+		(0, _) => a as u64,
+		(_, true) => 0,
+		(_, false) => (a % b) as u64
+	}
+}
+
+/// vd = vs1 * vs2 + vd
+pub fn macc(_context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	d.wrapping_add(a.wrapping_mul(b))
+}
+
+/// vd = -(vs1 * vs2) + vd
+pub fn nmsac(_context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	d.wrapping_sub(a.wrapping_mul(b))
+}
+
+/// vd = vs1 * vd + vs2
+pub fn madd(_context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	b.wrapping_mul(d).wrapping_add(a)
+}
+
+/// vd = -(vs1 * vd) + vs2
+pub fn nmsub(_context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	a.wrapping_sub(b.wrapping_mul(d))
+}
+
+/// Returns the `vs1` element or scalar. Used by `VMV.V.*`.
+pub fn move_operand(_context: &mut ElementContext, _d: u64, _a: u64, b: u64) -> u64 {
+	b
+}
+
+pub fn saddu(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	clamp_unsigned(context, a as i128 + b as i128, context.sew)
+}
+
+pub fn sadd(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let sum = sign_extend(a, context.sew) as i128 + sign_extend(b, context.sew) as i128;
+	clamp_signed(context, sum, context.sew)
+}
+
+pub fn ssubu(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	clamp_unsigned(context, a as i128 - b as i128, context.sew)
+}
+
+pub fn ssub(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let difference = sign_extend(a, context.sew) as i128 - sign_extend(b, context.sew) as i128;
+	clamp_signed(context, difference, context.sew)
+}
+
+pub fn aaddu(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	round_shift(a as i128 + b as i128, 1, context.vxrm) as u64
+}
+
+pub fn aadd(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let sum = sign_extend(a, context.sew) as i128 + sign_extend(b, context.sew) as i128;
+	round_shift(sum, 1, context.vxrm) as u64
+}
+
+pub fn asubu(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	round_shift(a as i128 - b as i128, 1, context.vxrm) as u64
+}
+
+pub fn asub(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let difference = sign_extend(a, context.sew) as i128 - sign_extend(b, context.sew) as i128;
+	round_shift(difference, 1, context.vxrm) as u64
+}
+
+/// Fractional multiply with rounding and saturation
+pub fn smul(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let product = sign_extend(a, context.sew) as i128 * sign_extend(b, context.sew) as i128;
+	let result = round_shift(product, context.sew as u32 - 1, context.vxrm);
+	clamp_signed(context, result, context.sew)
+}
+
+pub fn ssrl(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	round_shift(a as i128, (b & (context.sew as u64 - 1)) as u32, context.vxrm) as u64
+}
+
+pub fn ssra(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let shift = (b & (context.sew as u64 - 1)) as u32;
+	round_shift(sign_extend(a, context.sew) as i128, shift, context.vxrm) as u64
+}
+
+// Widening integer element operations. The results are 2 * SEW. a is
+// 2 * SEW for `*_w` operations.
+
+pub fn waddu(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.wrapping_add(b)
+}
+
+pub fn wadd(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	(sign_extend(a, context.sew) as u64).wrapping_add(sign_extend(b, context.sew) as u64)
+}
+
+pub fn wsubu(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.wrapping_sub(b)
+}
+
+pub fn wsub(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	(sign_extend(a, context.sew) as u64).wrapping_sub(sign_extend(b, context.sew) as u64)
+}
+
+pub fn waddu_w(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.wrapping_add(b)
+}
+
+pub fn wadd_w(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.wrapping_add(sign_extend(b, context.sew) as u64)
+}
+
+pub fn wsubu_w(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.wrapping_sub(b)
+}
+
+pub fn wsub_w(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.wrapping_sub(sign_extend(b, context.sew) as u64)
+}
+
+pub fn wmulu(_context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a.wrapping_mul(b)
+}
+
+pub fn wmul(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	(sign_extend(a, context.sew) as u64).wrapping_mul(sign_extend(b, context.sew) as u64)
+}
+
+/// Signed `vs2` * unsigned `vs1`
+pub fn wmulsu(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	(sign_extend(a, context.sew) as u64).wrapping_mul(b)
+}
+
+pub fn wmaccu(_context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	d.wrapping_add(a.wrapping_mul(b))
+}
+
+pub fn wmacc(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	d.wrapping_add(wmul(context, 0, a, b))
+}
+
+/// vd = signed `vs1` * unsigned `vs2` + vd
+pub fn wmaccsu(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	d.wrapping_add((sign_extend(b, context.sew) as u64).wrapping_mul(a))
+}
+
+/// vd = unsigned scalar * signed `vs2` + vd
+pub fn wmaccus(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	d.wrapping_add(wmulsu(context, 0, a, b))
+}
+
+// Narrowing integer element operations. a is 2 * SEW.
+
+pub fn nsrl(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a >> (b & (context.sew as u64 * 2 - 1))
+}
+
+pub fn nsra(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	(sign_extend(a, context.sew * 2) >> (b & (context.sew as u64 * 2 - 1))) as u64
+}
+
+pub fn nclipu(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let shift = (b & (context.sew as u64 * 2 - 1)) as u32;
+	let value = round_shift(a as i128, shift, context.vxrm);
+	clamp_unsigned(context, value, context.sew)
+}
+
+pub fn nclip(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let shift = (b & (context.sew as u64 * 2 - 1)) as u32;
+	let value = round_shift(sign_extend(a, context.sew * 2) as i128, shift, context.vxrm);
+	clamp_signed(context, value, context.sew)
+}
+
+// Integer comparisons
+
+pub fn mseq(_context: &mut ElementContext, a: u64, b: u64) -> bool {
+	a == b
+}
+
+pub fn msne(_context: &mut ElementContext, a: u64, b: u64) -> bool {
+	a != b
+}
+
+pub fn msltu(_context: &mut ElementContext, a: u64, b: u64) -> bool {
+	a < b
+}
+
+pub fn mslt(context: &mut ElementContext, a: u64, b: u64) -> bool {
+	sign_extend(a, context.sew) < sign_extend(b, context.sew)
+}
+
+pub fn msleu(_context: &mut ElementContext, a: u64, b: u64) -> bool {
+	a <= b
+}
+
+pub fn msle(context: &mut ElementContext, a: u64, b: u64) -> bool {
+	sign_extend(a, context.sew) <= sign_extend(b, context.sew)
+}
+
+pub fn msgtu(_context: &mut ElementContext, a: u64, b: u64) -> bool {
+	a > b
+}
+
+pub fn msgt(context: &mut ElementContext, a: u64, b: u64) -> bool {
+	sign_extend(a, context.sew) > sign_extend(b, context.sew)
+}
+
+// Operations with carry
+
+pub fn adc(_context: &mut ElementContext, a: u64, b: u64, carry: bool) -> u64 {
+	a.wrapping_add(b).wrapping_add(carry as u64)
+}
+
+pub fn sbc(_context: &mut ElementContext, a: u64, b: u64, borrow: bool) -> u64 {
+	a.wrapping_sub(b).wrapping_sub(borrow as u64)
+}
+
+/// Returns the `vs1` element or scalar if the mask bit is set, otherwise
+/// the `vs2` element. Used by `VMERGE` and `VFMERGE`.
+pub fn merge(_context: &mut ElementContext, a: u64, b: u64, mask: bool) -> u64 {
+	match mask {
+		true => b,
+		false => a
+	}
+}
+
+pub fn madc(context: &mut ElementContext, a: u64, b: u64, carry: bool) -> bool {
+	((a as u128 + b as u128 + carry as u128) >> context.sew) != 0
+}
+
+pub fn msbc(_context: &mut ElementContext, a: u64, b: u64, borrow: bool) -> bool {
+	(a as i128 - b as i128 - borrow as i128) < 0
+}
+
+// Floating-point element operations. SEW is 32 or 64.
+
+fn get_format(width: usize) -> &'static Format {
+	match width {
+		32 => &SINGLE,
+		_ => &DOUBLE
+	}
+}
+
+fn get_sign_bit(width: usize) -> u64 {
+	1 << (width - 1)
+}
+
+pub fn fadd(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	fpu::add(get_format(context.sew), a, b, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fsub(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	fpu::sub(get_format(context.sew), a, b, context.rounding_mode, &mut context.flags)
+}
+
+pub fn frsub(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	fpu::sub(get_format(context.sew), b, a, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fmul(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	fpu::mul(get_format(context.sew), a, b, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fdiv(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	fpu::div(get_format(context.sew), a, b, context.rounding_mode, &mut context.flags)
+}
+
+pub fn frdiv(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	fpu::div(get_format(context.sew), b, a, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fmin(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	fpu::min(get_format(context.sew), a, b, &mut context.flags)
+}
+
+pub fn fmax(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	fpu::max(get_format(context.sew), a, b, &mut context.flags)
+}
+
+pub fn fsgnj(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let sign_bit = get_sign_bit(context.sew);
+	(a & !sign_bit) | (b & sign_bit)
+}
+
+pub fn fsgnjn(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let sign_bit = get_sign_bit(context.sew);
+	(a & !sign_bit) | (!b & sign_bit)
+}
+
+pub fn fsgnjx(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	a ^ (b & get_sign_bit(context.sew))
+}
+
+/// Returns `a` * `b` + `c` negating the product and/or the addend
+fn fused_multiply_add(context: &mut ElementContext, width: usize, a: u64, b: u64, c: u64,
+	negate_product: bool, negate_addend: bool) -> u64 {
+	let format = get_format(width);
+	let a = match negate_product {
+		true => format.negate(a),
+		false => a
+	};
+	let c = match negate_addend {
+		true => format.negate(c),
+		false => c
+	};
+	fpu::mul_add(format, a, b, c, context.rounding_mode, &mut context.flags)
+}
+
+/// vd = +(vs1 * vs2) + vd
+pub fn fmacc(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	fused_multiply_add(context, context.sew, b, a, d, false, false)
+}
+
+/// vd = -(vs1 * vs2) - vd
+pub fn fnmacc(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	fused_multiply_add(context, context.sew, b, a, d, true, true)
+}
+
+/// vd = +(vs1 * vs2) - vd
+pub fn fmsac(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	fused_multiply_add(context, context.sew, b, a, d, false, true)
+}
+
+/// vd = -(vs1 * vs2) + vd
+pub fn fnmsac(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	fused_multiply_add(context, context.sew, b, a, d, true, false)
+}
+
+/// vd = +(vs1 * vd) + vs2
+pub fn fmadd(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	fused_multiply_add(context, context.sew, b, d, a, false, false)
+}
+
+/// vd = -(vs1 * vd) - vs2
+pub fn fnmadd(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	fused_multiply_add(context, context.sew, b, d, a, true, true)
+}
+
+/// vd = +(vs1 * vd) - vs2
+pub fn fmsub(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	fused_multiply_add(context, context.sew, b, d, a, false, true)
+}
+
+/// vd = -(vs1 * vd) + vs2
+pub fn fnmsub(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	fused_multiply_add(context, context.sew, b, d, a, true, false)
+}
+
+// Widening floating-point element operations. SEW is 32 and the results
+// are double-precision.
+
+fn widen(context: &mut ElementContext, value: u64) -> u64 {
+	fpu::convert(&SINGLE, &DOUBLE, value, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fwadd(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let a = widen(context, a);
+	let b = widen(context, b);
+	fpu::add(&DOUBLE, a, b, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fwsub(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let a = widen(context, a);
+	let b = widen(context, b);
+	fpu::sub(&DOUBLE, a, b, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fwadd_w(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let b = widen(context, b);
+	fpu::add(&DOUBLE, a, b, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fwsub_w(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let b = widen(context, b);
+	fpu::sub(&DOUBLE, a, b, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fwmul(context: &mut ElementContext, _d: u64, a: u64, b: u64) -> u64 {
+	let a = widen(context, a);
+	let b = widen(context, b);
+	fpu::mul(&DOUBLE, a, b, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fwmacc(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	let a = widen(context, a);
+	let b = widen(context, b);
+	fused_multiply_add(context, 64, b, a, d, false, false)
+}
+
+pub fn fwnmacc(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	let a = widen(context, a);
+	let b = widen(context, b);
+	fused_multiply_add(context, 64, b, a, d, true, true)
+}
+
+pub fn fwmsac(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	let a = widen(context, a);
+	let b = widen(context, b);
+	fused_multiply_add(context, 64, b, a, d, false, true)
+}
+
+pub fn fwnmsac(context: &mut ElementContext, d: u64, a: u64, b: u64) -> u64 {
+	let a = widen(context, a);
+	let b = widen(context, b);
+	fused_multiply_add(context, 64, b, a, d, true, false)
+}
+
+// Floating-point comparisons
+
+pub fn mfeq(context: &mut ElementContext, a: u64, b: u64) -> bool {
+	fpu::eq(get_format(context.sew), a, b, &mut context.flags)
+}
+
+pub fn mfne(context: &mut ElementContext, a: u64, b: u64) -> bool {
+	!fpu::eq(get_format(context.sew), a, b, &mut context.flags)
+}
+
+pub fn mflt(context: &mut ElementContext, a: u64, b: u64) -> bool {
+	fpu::lt(get_format(context.sew), a, b, &mut context.flags)
+}
+
+pub fn mfle(context: &mut ElementContext, a: u64, b: u64) -> bool {
+	fpu::le(get_format(context.sew), a, b, &mut context.flags)
+}
+
+pub fn mfgt(context: &mut ElementContext, a: u64, b: u64) -> bool {
+	fpu::lt(get_format(context.sew), b, a, &mut context.flags)
+}
+
+pub fn mfge(context: &mut ElementContext, a: u64, b: u64) -> bool {
+	fpu::le(get_format(context.sew), b, a, &mut context.flags)
+}
+
+// Unary floating-point element operations and conversions. Widening
+// conversions produce 2 * SEW and narrowing ones take 2 * SEW.
+
+pub fn fsqrt(context: &mut ElementContext, a: u64) -> u64 {
+	fpu::sqrt(get_format(context.sew), a, context.rounding_mode, &mut context.flags)
+}
+
+pub fn frsqrt7(context: &mut ElementContext, a: u64) -> u64 {
+	fpu::reciprocal_sqrt_estimate(get_format(context.sew), a, &mut context.flags)
+}
+
+pub fn frec7(context: &mut ElementContext, a: u64) -> u64 {
+	fpu::reciprocal_estimate(get_format(context.sew), a, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fclass(context: &mut ElementContext, a: u64) -> u64 {
+	fpu::classify(get_format(context.sew), a)
+}
+
+/// Converts floating-point value to integer
+fn float_to_integer(context: &mut ElementContext, a: u64, from_width: usize, to_width: usize,
+	signed: bool, truncating: bool) -> u64 {
+	let rounding_mode = match truncating {
+		true => RoundingMode::TowardZero,
+		false => context.rounding_mode
+	};
+	fpu::to_integer(get_format(from_width), a, to_width as u32, signed, rounding_mode, &mut context.flags)
+}
+
+/// Converts integer to floating-point value
+fn integer_to_float(context: &mut ElementContext, a: u64, from_width: usize, to_width: usize,
+	signed: bool) -> u64 {
+	let value = match signed {
+		true => sign_extend(a, from_width) as u64,
+		false => a
+	};
+	fpu::from_integer(get_format(to_width), value, signed, context.rounding_mode, &mut context.flags)
+}
+
+pub fn fcvt_xu_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew, context.sew, false, false)
+}
+
+pub fn fcvt_x_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew, context.sew, true, false)
+}
+
+pub fn fcvt_rtz_xu_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew, context.sew, false, true)
+}
+
+pub fn fcvt_rtz_x_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew, context.sew, true, true)
+}
+
+pub fn fcvt_f_xu(context: &mut ElementContext, a: u64) -> u64 {
+	integer_to_float(context, a, context.sew, context.sew, false)
+}
+
+pub fn fcvt_f_x(context: &mut ElementContext, a: u64) -> u64 {
+	integer_to_float(context, a, context.sew, context.sew, true)
+}
+
+pub fn fwcvt_xu_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew, context.sew * 2, false, false)
+}
+
+pub fn fwcvt_x_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew, context.sew * 2, true, false)
+}
+
+pub fn fwcvt_rtz_xu_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew, context.sew * 2, false, true)
+}
+
+pub fn fwcvt_rtz_x_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew, context.sew * 2, true, true)
+}
+
+pub fn fwcvt_f_xu(context: &mut ElementContext, a: u64) -> u64 {
+	integer_to_float(context, a, context.sew, context.sew * 2, false)
+}
+
+pub fn fwcvt_f_x(context: &mut ElementContext, a: u64) -> u64 {
+	integer_to_float(context, a, context.sew, context.sew * 2, true)
+}
+
+pub fn fwcvt_f_f(context: &mut ElementContext, a: u64) -> u64 {
+	widen(context, a)
+}
+
+pub fn fncvt_xu_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew * 2, context.sew, false, false)
+}
+
+pub fn fncvt_x_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew * 2, context.sew, true, false)
+}
+
+pub fn fncvt_rtz_xu_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew * 2, context.sew, false, true)
+}
+
+pub fn fncvt_rtz_x_f(context: &mut ElementContext, a: u64) -> u64 {
+	float_to_integer(context, a, context.sew * 2, context.sew, true, true)
+}
+
+pub fn fncvt_f_xu(context: &mut ElementContext, a: u64) -> u64 {
+	integer_to_float(context, a, context.sew * 2, context.sew, false)
+}
+
+pub fn fncvt_f_x(context: &mut ElementContext, a: u64) -> u64 {
+	integer_to_float(context, a, context.sew * 2, context.sew, true)
+}
+
+pub fn fncvt_f_f(context: &mut ElementContext, a: u64) -> u64 {
+	fpu::convert(&DOUBLE, &SINGLE, a, context.rounding_mode, &mut context.flags)
+}
+
+/// Narrows double-precision value rounding to odd
+pub fn fncvt_rod_f_f(context: &mut ElementContext, a: u64) -> u64 {
+	// Rounding toward zero and then setting the least significant bit
+	// if inexact is equivalent to rounding to odd
+	let mut flags = 0;
+	let value = fpu::convert(&DOUBLE, &SINGLE, a, RoundingMode::TowardZero, &mut flags);
+	context.flags |= flags;
+	match (flags & fpu::FLAG_NX) != 0 && !SINGLE.is_infinite(value) {
+		true => value | 1,
+		false => value
+	}
+}
+
+#[cfg(test)]
+mod test_vpu {
+	use super::*;
+
+	fn create_context(sew: usize) -> ElementContext {
+		ElementContext {
+			sew,
+			vxrm: VXRM_RNU,
+			saturated: false,
+			rounding_mode: RoundingMode::NearestEven,
+			flags: 0
+		}
+	}
+
+	fn create_instruction(vd: usize, vs2: usize, operand: Operand, masked: bool) -> VectorInstruction {
+		VectorInstruction {
+			vd,
+			vs2,
+			operand,
+			masked
+		}
+	}
+
+	// vtype encoding of SEW and LMUL
+	fn vtype(sew: usize, lmul_encoding: u64) -> u64 {
+		((sew.trailing_zeros() as u64 - 3) << 3) | lmul_encoding
+	}
+
+	#[test]
+	fn set_vtype() {
+		let mut vpu = Vpu::new(128);
+		assert!(vpu.is_vill());
+		assert_eq!(1 << 63, vpu.read_vtype(&Xlen::Bit64));
+		assert_eq!(16, vpu.get_vlenb());
+		assert_eq!(4, vpu.set_vtype(vtype(32, 0), Some(100)));
+		assert_eq!(3, vpu.set_vtype(vtype(32, 0), Some(3)));
+		// LMUL=8
+		assert_eq!(64, vpu.set_vtype(vtype(16, 3), Some(100)));
+		assert_eq!(64, vpu.get_vlmax());
+		// LMUL=1/2, keeping vl is limited to the new VLMAX
+		assert_eq!(4, vpu.set_vtype(vtype(16, 7), None));
+		assert_eq!(vtype(16, 7), vpu.read_vtype(&Xlen::Bit32));
+		// SEW=64 with LMUL=1/2 is unsupported
+		assert_eq!(0, vpu.set_vtype(vtype(64, 7), Some(1)));
+		assert!(vpu.is_vill());
+		assert_eq!(1 << 31, vpu.read_vtype(&Xlen::Bit32));
+		// Reserved LMUL
+		vpu.set_vtype(vtype(8, 0), Some(1));
+		assert!(!vpu.is_vill());
+		vpu.set_vtype(vtype(8, 4), Some(1));
+		assert!(vpu.is_vill());
+		// Reserved bits
+		vpu.set_vtype(0x100, Some(1));
+		assert!(vpu.is_vill());
+	}
+
+	#[test]
+	fn fixed_point() {
+		let mut context = create_context(8);
+		// (vxrm, expected) for 0b1011 >> 2 and 0b1010 >> 2
+		let cases = [(VXRM_RNU, 3, 3), (VXRM_RNE, 3, 2), (VXRM_RDN, 2, 2), (3, 3, 3)];
+		for &(vxrm, expected_a, expected_b) in cases.iter() {
+			context.vxrm = vxrm;
+			assert_eq!(expected_a, ssrl(&mut context, 0, 0xb, 2), "vxrm {}", vxrm);
+			assert_eq!(expected_b, ssrl(&mut context, 0, 0xa, 2), "vxrm {}", vxrm);
+		}
+		context.vxrm = VXRM_RNU;
+		assert_eq!(0xff, truncate(aadd(&mut context, 0, 0xff, 0xfe), 8)); // (-1 + -2) / 2 rounded
+		assert_eq!(0x81, truncate(asubu(&mut context, 0, 0x00, 0xff), 8)); // (0 - 255) / 2 rounded
+		assert!(!context.saturated);
+		assert_eq!(0x7f, smul(&mut context, 0, 0x80, 0x80));
+		assert!(context.saturated);
+		context.saturated = false;
+		assert_eq!(0x20, smul(&mut context, 0, 0x40, 0x40)); // 0.5 * 0.5
+		assert_eq!(0xff, saddu(&mut context, 0, 0xf0, 0x20));
+		assert_eq!(0, ssubu(&mut context, 0, 0x10, 0x20));
+		assert_eq!(0x80, truncate(sadd(&mut context, 0, 0x80, 0xff), 8));
+		assert_eq!(0x7f, ssub(&mut context, 0, 0x7f, 0xff));
+		assert_eq!(0xff, nclipu(&mut context, 0, 0x1234, 4));
+		assert_eq!(0x80, truncate(nclip(&mut context, 0, 0xf123, 4), 8));
+		assert!(context.saturated);
+	}
+
+	#[test]
+	fn integer_arithmetic() {
+		let mut context = create_context(8);
+		assert_eq!(0xff, truncate(div(&mut context, 0, 0x80, 0), 8));
+		assert_eq!(0x80, truncate(div(&mut context, 0, 0x80, 0xff), 8));
+		assert_eq!(0, rem(&mut context, 0, 0x80, 0xff));
+		assert_eq!(0xfe, truncate(div(&mut context, 0, 0xfa, 0x03), 8));
+		assert_eq!(0x12, remu(&mut context, 0, 0x12, 0));
+		assert_eq!(0xff, truncate(mulh(&mut context, 0, 0xff, 0x01), 8));
+		assert_eq!(0x00, truncate(mulhu(&mut context, 0, 0xff, 0x01), 8));
+		assert_eq!(0xff, truncate(mulhsu(&mut context, 0, 0xff, 0x01), 8));
+		assert_eq!(0xc0, truncate(sra(&mut context, 0, 0x80, 9), 8));
+		assert_eq!(0x80, min(&mut context, 0, 0x80, 0x7f));
+		assert_eq!(0x7f, minu(&mut context, 0, 0x80, 0x7f));
+		assert_eq!(0xfffe, truncate(wmul(&mut context, 0, 0xff, 0x02), 16));
+		assert_eq!(0x01fe, truncate(wmulu(&mut context, 0, 0xff, 0x02), 16));
+		assert_eq!(0xfffe, truncate(wmulsu(&mut context, 0, 0xff, 0x02), 16));
+		assert_eq!(0x10ff, truncate(wmaccus(&mut context, 0x1000, 0x01, 0xff), 16));
+		assert_eq!(0x0fff, truncate(wmaccsu(&mut context, 0x1000, 0x01, 0xff), 16));
+		assert!(madc(&mut context, 0xff, 0x00, true));
+		assert!(!madc(&mut context, 0xfe, 0x00, true));
+		assert!(msbc(&mut context, 0x00, 0x00, true));
+		assert!(mslt(&mut context, 0x80, 0x00));
+		assert!(!msltu(&mut context, 0x80, 0x00));
+	}
+
+	#[test]
+	fn element_loop() {
+		let mut vpu = Vpu::new(128);
+		let mut context = create_context(32);
+		vpu.set_vtype(vtype(32, 1), Some(6)); // LMUL=2
+		for i in 0..8 {
+			vpu.write_element(2, i, 32, i as u64 + 1);
+			vpu.write_element(4, i, 32, 10);
+		}
+		vpu.write_element(0, 0, 8, 0x55); // v0 mask
+		// Misaligned register group
+		assert!(vpu.binary(&mut context, &create_instruction(3, 2, Operand::Vector(4), false), add).is_err());
+		// Masked instruction can't overwrite v0
+		assert!(vpu.binary(&mut context, &create_instruction(0, 2, Operand::Vector(4), true), add).is_err());
+		vpu.write_vstart(1);
+		vpu.binary(&mut context, &create_instruction(6, 2, Operand::Scalar(-1i64 as u64), true), add).unwrap();
+		// Element 0 is before vstart, 6 and 7 are tail
+		let expected = [0, 0, 2, 0, 4, 0, 0, 0];
+		for (i, value) in expected.iter().enumerate() {
+			assert_eq!(*value, vpu.read_element(6, i, 32), "{}", i);
+		}
+		vpu.write_vstart(0);
+		vpu.widening(&mut context, &create_instruction(8, 2, Operand::Vector(4), false), false, wmulu).unwrap();
+		assert_eq!(60, vpu.read_element(8, 5, 64));
+		// LMUL=2 can't be widened in 32-bit register groups from v6
+		assert!(vpu.widening(&mut context, &create_instruction(6, 2, Operand::Vector(4), false), false, wmulu).is_err());
+		vpu.compare(&mut context, &create_instruction(0, 2, Operand::Scalar(3), false), msgtu).unwrap();
+		// The tail element 6 is undisturbed
+		assert_eq!(0x78, vpu.read_element(0, 0, 8));
+		vpu.reduction(&mut context, &create_instruction(10, 2, Operand::Vector(4), false), false, add).unwrap();
+		assert_eq!(31, vpu.read_element(10, 0, 32));
+	}
+
+	#[test]
+	fn permutation() {
+		let mut vpu = Vpu::new(128);
+		vpu.set_vtype(vtype(16, 0), Some(8));
+		for i in 0..8 {
+			vpu.write_element(2, i, 16, i as u64 + 1);
+			vpu.write_element(3, i, 16, 7 - i as u64);
+		}
+		vpu.slide_up(&create_instruction(4, 2, Operand::Scalar(3), false)).unwrap();
+		assert_eq!([0, 0, 0, 1, 2, 3, 4, 5], read_elements(&vpu, 4));
+		vpu.slide_down(&create_instruction(4, 2, Operand::Scalar(3), false)).unwrap();
+		assert_eq!([4, 5, 6, 7, 8, 0, 0, 0], read_elements(&vpu, 4));
+		vpu.slide1_up(&create_instruction(4, 2, Operand::Scalar(9), false)).unwrap();
+		assert_eq!([9, 1, 2, 3, 4, 5, 6, 7], read_elements(&vpu, 4));
+		vpu.slide1_down(&create_instruction(4, 2, Operand::Scalar(9), false)).unwrap();
+		assert_eq!([2, 3, 4, 5, 6, 7, 8, 9], read_elements(&vpu, 4));
+		assert!(vpu.slide_up(&create_instruction(2, 2, Operand::Scalar(1), false)).is_err());
+		vpu.gather(&create_instruction(4, 2, Operand::Vector(3), false), 16).unwrap();
+		assert_eq!([8, 7, 6, 5, 4, 3, 2, 1], read_elements(&vpu, 4));
+		vpu.gather(&create_instruction(4, 2, Operand::Scalar(100), false), 16).unwrap();
+		assert_eq!([0; 8], read_elements(&vpu, 4));
+		vpu.write_element(1, 0, 8, 0x96);
+		vpu.compress(&create_instruction(4, 2, Operand::Vector(1), false)).unwrap();
+		assert_eq!([2, 3, 5, 8, 0, 0, 0, 0], read_elements(&vpu, 4));
+		vpu.iota(&create_instruction(4, 1, Operand::Vector(0), false)).unwrap();
+		assert_eq!([0, 0, 1, 2, 2, 3, 3, 3], read_elements(&vpu, 4));
+		let instruction = create_instruction(5, 1, Operand::Vector(0), false);
+		assert_eq!(4, vpu.count_population(&instruction).unwrap());
+		assert_eq!(1, vpu.find_first(&instruction).unwrap());
+		vpu.set_mask_by_first(&instruction, |found, _is_first| !found).unwrap(); // vmsif
+		assert_eq!(0x03, vpu.read_element(5, 0, 8));
+		vpu.set_mask_by_first(&instruction, |found, is_first| !found && !is_first).unwrap(); // vmsbf
+		assert_eq!(0x01, vpu.read_element(5, 0, 8));
+		vpu.move_registers(&create_instruction(6, 2, Operand::Scalar(1), false), 2).unwrap();
+		assert_eq!(vpu.read_element(3, 7, 16), vpu.read_element(7, 7, 16));
+	}
+
+	fn read_elements(vpu: &Vpu, register: usize) -> [u64; 8] {
+		let mut elements = [0; 8];
+		for (i, element) in elements.iter_mut().enumerate() {
+			*element = vpu.read_element(register, i, 16);
+		}
+		elements
+	}
+
+	#[test]
+	fn floating_point() {
+		let mut context = create_context(32);
+		let s = |value: f32| value.to_bits() as u64;
+		let d = |value: f64| value.to_bits();
+		assert_eq!(s(7.0), fmacc(&mut context, s(1.0), s(2.0), s(3.0)));
+		assert_eq!(s(-7.0), fnmacc(&mut context, s(1.0), s(2.0), s(3.0)));
+		assert_eq!(s(5.0), fmadd(&mut context, s(1.0), s(2.0), s(3.0)));
+		assert_eq!(s(-1.0), fnmsub(&mut context, s(1.0), s(2.0), s(3.0)));
+		assert_eq!(s(-2.0), fsgnjn(&mut context, 0, s(2.0), s(1.0)));
+		assert_eq!(d(7.0), fwmacc(&mut context, d(1.0), s(2.0), s(3.0)));
+		assert_eq!(d(3.5), fwadd_w(&mut context, 0, d(1.5), s(2.0)));
+		assert!(mfgt(&mut context, s(2.0), s(1.0)));
+		assert_eq!(0, context.flags);
+		context.sew = 16;
+		assert_eq!(0x7fff, truncate(fncvt_x_f(&mut context, s(1e9)), 16));
+		assert_eq!(fpu::FLAG_NV, context.flags);
+		// 1 + 2^-30 is rounded to odd, 1 + 2^-23
+		context.sew = 32;
+		assert_eq!(s(1.0) | 1, fncvt_rod_f_f(&mut context, d(1.0 + 1.0 / 1073741824.0)));
+	}
+}
@@ -63,21 +63,28 @@ impl WasmRiscv {
 	}
 
 	/// Sets up program run by the program. This method is expected to be called
-	/// only once.
+	/// only once. Throws an error if the content isn't a valid RISC-V ELF file.
 	///
 	/// # Arguments
 	/// * `content` Program binary
-	pub fn setup_program(&mut self, content: Vec<u8>) {
-		self.emulator.setup_program(content);
+	pub fn setup_program(&mut self, content: Vec<u8>) -> Result<(), JsValue> {
+		match self.emulator.setup_program(content) {
+			Ok(()) => Ok(()),
+			Err(e) => Err(JsValue::from_str(&e.to_string()))
+		}
 	}
 
 	/// Loads symbols of program and adds them to symbol - virtual address
-	/// mapping in `Emulator`.
+	/// mapping in `Emulator`. Throws an error if the content isn't a valid
+	/// RISC-V ELF file.
 	///
 	/// # Arguments
 	/// * `content` Program binary
-	pub fn load_program_for_symbols(&mut self, content: Vec<u8>) {
-		self.emulator.load_program_for_symbols(content);
+	pub fn load_program_for_symbols(&mut self, content: Vec<u8>) -> Result<(), JsValue> {
+		match self.emulator.load_program_for_symbols(content) {
+			Ok(()) => Ok(()),
+			Err(e) => Err(JsValue::from_str(&e.to_string()))
+		}
 	}
 
 	/// Sets up filesystem. Use this method if program (e.g. Linux) uses
@@ -247,16 +254,6 @@ impl WasmRiscv {
 		self.emulator.get_mut_terminal().put_input(data);
 	}
 
-	/// Enables or disables page cache optimization.
-	/// Page cache optimization is an experimental feature.
-	/// Refer to [`Mmu`](../riscv_emu_rust/mmu/struct.Mmu.html) for the detail.
-	///
-	/// # Arguments
-	/// * `enabled`
-	pub fn enable_page_cache(&mut self, enabled: bool) {
-		self.emulator.enable_page_cache(enabled);
-	}
-
 	/// Gets virtual address corresponding to symbol strings.
 	///
 	/// # Arguments